@@ -0,0 +1,189 @@
+use consistency::ConsistencyProof;
+use digest::Digest;
+use hash_utils::{HashMode, HashScheme, OddNodePolicy};
+use hasher::{MerkleHasher, Sha256};
+use leaf::AsLeafBytes;
+use merkle_tree::{MerkleTree, ProofNode};
+use multiproof::MultiProof;
+use proof::Proof;
+use range_proof::RangeProof;
+use root_builder::MerkleRootBuilder;
+use sorted_tree::SortedMerkleTree;
+
+/// Builder of `MerkleTree` with custom hash function and hashing rules.
+/// # Examples
+///
+/// ```
+/// extern crate merkle_tree;
+/// use merkle_tree::{Blake2b, HashMode, MerkleTreeBuilder};
+///
+/// let tree = MerkleTreeBuilder::new()
+///     .hasher(Blake2b)
+///     .hash_mode(HashMode::Compat)
+///     .build_from_vec(vec![1, 2, 3, 4]);
+/// assert_eq!(4, tree.len());
+/// ```
+#[derive(Clone, Debug)]
+pub struct MerkleTreeBuilder<H: MerkleHasher = Sha256> {
+    hasher: H,
+    hash_mode: HashMode,
+    odd_node_policy: OddNodePolicy,
+    empty_root: Option<Digest>,
+}
+
+impl MerkleTreeBuilder {
+    /// Creates new builder with SHA-256 hash function and default hashing rules.
+    pub fn new() -> Self {
+        MerkleTreeBuilder {
+            hasher: Sha256,
+            hash_mode: HashMode::default(),
+            odd_node_policy: OddNodePolicy::default(),
+            empty_root: None,
+        }
+    }
+}
+
+impl Default for MerkleTreeBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<H: MerkleHasher> MerkleTreeBuilder<H> {
+    /// Sets the hash function used to calculate hashes of leaves and nodes.
+    pub fn hasher<G: MerkleHasher>(self, hasher: G) -> MerkleTreeBuilder<G> {
+        MerkleTreeBuilder {
+            hasher: hasher,
+            hash_mode: self.hash_mode,
+            odd_node_policy: self.odd_node_policy,
+            empty_root: self.empty_root,
+        }
+    }
+
+    /// Sets the way hashes of the nodes are combined. `HashMode::Binary` by default.
+    pub fn hash_mode(mut self, hash_mode: HashMode) -> Self {
+        self.hash_mode = hash_mode;
+        self
+    }
+
+    /// Sets the way the last node of a level without sibling is handled.
+    /// `OddNodePolicy::Duplicate` by default.
+    pub fn odd_node_policy(mut self, odd_node_policy: OddNodePolicy) -> Self {
+        self.odd_node_policy = odd_node_policy;
+        self
+    }
+
+    /// Sets the root hash of the empty tree. By default it's the all-zero hash,
+    /// `H("")` as defined by RFC 6962 in `HashMode::DomainSeparated` or `H("0")` in
+    /// `HashMode::Compat`.
+    pub fn empty_root(mut self, empty_root: Digest) -> Self {
+        self.empty_root = Some(empty_root);
+        self
+    }
+
+    /// Creates new, empty `MerkleTree`.
+    pub fn build<T: AsLeafBytes + Clone>(self) -> MerkleTree<T, H> {
+        MerkleTree::with_scheme(self.scheme())
+    }
+
+    /// Creates `MerkleTree` from `Vec` of elements.
+    pub fn build_from_vec<T: AsLeafBytes + Clone>(self, data: Vec<T>) -> MerkleTree<T, H> {
+        MerkleTree::from_vec_with_scheme(data, self.scheme())
+    }
+
+    /// Creates new, empty `SortedMerkleTree`. It always uses `HashMode::DomainSeparated`,
+    /// since absence proofs can be forged if leaves can be mistaken for nodes.
+    pub fn build_sorted<T: AsLeafBytes + Clone + Ord>(self) -> SortedMerkleTree<T, H> {
+        SortedMerkleTree::with_scheme(self.sorted_scheme())
+    }
+
+    /// Creates `SortedMerkleTree` from `Vec` of elements. It always uses
+    /// `HashMode::DomainSeparated`, like `build_sorted` does.
+    pub fn build_sorted_from_vec<T>(self, data: Vec<T>) -> SortedMerkleTree<T, H>
+        where T: AsLeafBytes + Clone + Ord
+    {
+        SortedMerkleTree::from_vec_with_scheme(data, self.sorted_scheme())
+    }
+
+    /// Creates `MerkleRootBuilder` which calculates the root hash with the same hash
+    /// function and hashing rules without storing the values.
+    pub fn root_builder(self) -> MerkleRootBuilder<H> {
+        MerkleRootBuilder::with_scheme(self.scheme())
+    }
+
+    /// Creates `Proof` received from elsewhere, e.g. from a server, which is validated
+    /// with the hash function and hashing rules of the builder.
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate merkle_tree;
+    /// use merkle_tree::{HashMode, MerkleTreeBuilder, OddNodePolicy};
+    ///
+    /// let builder = MerkleTreeBuilder::new()
+    ///     .hash_mode(HashMode::DomainSeparated)
+    ///     .odd_node_policy(OddNodePolicy::Promote);
+    /// let tree = builder.clone().build_from_vec(vec![1, 2, 3]);
+    /// let sent = tree.get_proof(3).unwrap();
+    /// let proof = builder.proof(*sent.root_hash(),
+    ///                           3,
+    ///                           sent.leaf_index(),
+    ///                           sent.tree_size(),
+    ///                           sent.path().to_vec());
+    /// assert!(proof.validate_strict(tree.root_hash().unwrap()));
+    /// ```
+    pub fn proof<T: AsLeafBytes>(&self,
+                                 root_hash: Digest,
+                                 value: T,
+                                 leaf_index: usize,
+                                 tree_size: usize,
+                                 path: Vec<ProofNode>)
+                                 -> Proof<T, H> {
+        let scheme = self.clone().scheme();
+        Proof::with_scheme(root_hash, value, leaf_index, tree_size, path, scheme)
+    }
+
+    /// Creates `ConsistencyProof` received from elsewhere, which is verified with the hash
+    /// function and hashing rules of the builder.
+    pub fn consistency_proof(&self,
+                             old_size: usize,
+                             new_size: usize,
+                             path: Vec<Digest>)
+                             -> ConsistencyProof<H> {
+        ConsistencyProof::with_scheme(old_size, new_size, path, self.clone().scheme())
+    }
+
+    /// Creates `MultiProof` received from elsewhere, which is validated with the hash
+    /// function and hashing rules of the builder.
+    pub fn multiproof<T: AsLeafBytes>(&self,
+                                      root_hash: Digest,
+                                      values: Vec<(usize, T)>,
+                                      tree_size: usize,
+                                      hashes: Vec<Digest>)
+                                      -> MultiProof<T, H> {
+        let scheme = self.clone().scheme();
+        MultiProof::with_scheme(root_hash, values, tree_size, hashes, scheme)
+    }
+
+    /// Creates `RangeProof` received from elsewhere, which is validated with the hash
+    /// function and hashing rules of the builder.
+    pub fn range_proof(&self,
+                       start: usize,
+                       end: usize,
+                       tree_size: usize,
+                       hashes: Vec<Digest>)
+                       -> RangeProof<H> {
+        RangeProof::with_scheme(start, end, tree_size, hashes, self.clone().scheme())
+    }
+
+    fn sorted_scheme(self) -> HashScheme<H> {
+        self.hash_mode(HashMode::DomainSeparated).scheme()
+    }
+
+    fn scheme(self) -> HashScheme<H> {
+        let scheme = HashScheme::new(self.hasher, self.hash_mode, self.odd_node_policy);
+        match self.empty_root {
+            Some(empty_root) => scheme.with_empty_root(empty_root),
+            None => scheme,
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use digest::Digest;
+use hash_utils::{HashMode, HashScheme, OddNodePolicy};
+use hasher::{MerkleHasher, Sha256};
+
+/// Proof that the tree of `old_size` elements is a prefix of the tree of `new_size`
+/// elements, as defined by RFC 6962. Built by `MerkleTree::get_consistency_proof`.
+/// # Examples
+///
+/// ```
+/// extern crate merkle_tree;
+/// use merkle_tree::{HashMode, MerkleTreeBuilder, OddNodePolicy};
+///
+/// let builder = MerkleTreeBuilder::new()
+///     .hash_mode(HashMode::DomainSeparated)
+///     .odd_node_policy(OddNodePolicy::Promote);
+/// let old_tree = builder.clone().build_from_vec(vec![1, 2, 3]);
+/// let new_tree = builder.build_from_vec(vec![1, 2, 3, 4, 5]);
+/// let proof = new_tree.get_consistency_proof(3, 5).unwrap();
+/// assert!(proof.verify(old_tree.root_hash().unwrap(), new_tree.root_hash().unwrap()));
+/// ```
+#[derive(Debug)]
+pub struct ConsistencyProof<H: MerkleHasher = Sha256> {
+    old_size: usize,
+    new_size: usize,
+    path: Vec<Digest>,
+    scheme: HashScheme<H>,
+}
+
+impl ConsistencyProof {
+    /// Creates new consistency proof between trees of `old_size` and `new_size` elements
+    /// hashed with SHA-256 as defined by RFC 6962, e.g. received from a log server.
+    /// Use `MerkleTreeBuilder::consistency_proof` for other hashing rules.
+    pub fn new(old_size: usize, new_size: usize, path: Vec<Digest>) -> Self {
+        let scheme = HashScheme::new(Sha256, HashMode::DomainSeparated, OddNodePolicy::Promote);
+        Self::with_scheme(old_size, new_size, path, scheme)
+    }
+}
+
+impl<H: MerkleHasher> ConsistencyProof<H> {
+    pub(crate) fn with_scheme(old_size: usize,
+                              new_size: usize,
+                              path: Vec<Digest>,
+                              scheme: HashScheme<H>)
+                              -> Self {
+        ConsistencyProof {
+            old_size: old_size,
+            new_size: new_size,
+            path: path,
+            scheme: scheme,
+        }
+    }
+
+    /// Returns the number of elements in the old tree.
+    pub fn old_size(&self) -> usize {
+        self.old_size
+    }
+
+    /// Returns the number of elements in the new tree.
+    pub fn new_size(&self) -> usize {
+        self.new_size
+    }
+
+    /// Returns hashes of the subtrees the proof consists of.
+    pub fn path(&self) -> &[Digest] {
+        &self.path
+    }
+
+    /// Returns `true` if the tree with `old_root` is a prefix of the tree with `new_root`.
+    /// Follows the verification algorithm of RFC 9162, section 2.1.4.2.
+    pub fn verify(&self, old_root: &Digest, new_root: &Digest) -> bool {
+        if self.old_size == 0 || self.old_size > self.new_size {
+            return false;
+        }
+        if self.old_size == self.new_size {
+            return self.path.is_empty() && old_root == new_root;
+        }
+
+        // The old root is omitted from the proof if the old tree is a complete subtree.
+        let mut path = self.path.iter();
+        let first = if self.old_size.is_power_of_two() {
+            Some(old_root)
+        } else {
+            path.next()
+        };
+        let first = match first {
+            Some(hash) => *hash,
+            None => return false,
+        };
+
+        let mut old_index = self.old_size - 1;
+        let mut new_index = self.new_size - 1;
+        while old_index & 1 == 1 {
+            old_index >>= 1;
+            new_index >>= 1;
+        }
+
+        let mut old_hash = first;
+        let mut new_hash = first;
+        for hash in path {
+            if new_index == 0 {
+                return false;
+            }
+            if old_index & 1 == 1 || old_index == new_index {
+                old_hash = self.scheme.node(hash, &old_hash);
+                new_hash = self.scheme.node(hash, &new_hash);
+                while old_index & 1 == 0 && old_index != 0 {
+                    old_index >>= 1;
+                    new_index >>= 1;
+                }
+            } else {
+                new_hash = self.scheme.node(&new_hash, hash);
+            }
+            old_index >>= 1;
+            new_index >>= 1;
+        }
+
+        new_index == 0 && old_hash == *old_root && new_hash == *new_root
+    }
+}
@@ -0,0 +1,96 @@
+use std::error::Error;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::str::FromStr;
+
+/// Size of the `Digest` in bytes.
+pub const DIGEST_SIZE: usize = 32;
+
+/// Fixed-size binary hash of a leaf or a node of the `MerkleTree`.
+/// Formatted and parsed as a lowercase hex string.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Digest([u8; DIGEST_SIZE]);
+
+impl Digest {
+    /// Creates `Digest` from raw bytes.
+    pub fn new(bytes: [u8; DIGEST_SIZE]) -> Self {
+        Digest(bytes)
+    }
+
+    /// Creates `Digest` from a slice, returns `None` if the slice has wrong length.
+    pub fn from_slice(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() == DIGEST_SIZE {
+            let mut digest = [0; DIGEST_SIZE];
+            digest.copy_from_slice(bytes);
+            Some(Digest(digest))
+        } else {
+            None
+        }
+    }
+
+    /// Returns raw bytes of the digest.
+    pub fn as_bytes(&self) -> &[u8; DIGEST_SIZE] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for Digest {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Display for Digest {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for byte in &self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl Debug for Digest {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "Digest({})", self)
+    }
+}
+
+impl FromStr for Digest {
+    type Err = ParseDigestError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.as_bytes();
+        if hex.len() != DIGEST_SIZE * 2 {
+            return Err(ParseDigestError(()));
+        }
+
+        let mut digest = [0; DIGEST_SIZE];
+        for (i, byte) in digest.iter_mut().enumerate() {
+            match (hex_value(hex[i * 2]), hex_value(hex[i * 2 + 1])) {
+                (Some(high), Some(low)) => *byte = high << 4 | low,
+                _ => return Err(ParseDigestError(())),
+            }
+        }
+        Ok(Digest(digest))
+    }
+}
+
+fn hex_value(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Error returned when a string is not a valid hex encoded `Digest`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseDigestError(());
+
+impl Display for ParseDigestError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "invalid hex encoded digest")
+    }
+}
+
+impl Error for ParseDigestError {}
@@ -1,22 +1,152 @@
-use crypto::digest::Digest;
-use crypto::sha2::Sha256;
-use std::string::ToString;
+use digest::{DIGEST_SIZE, Digest};
+use hasher::MerkleHasher;
+use leaf::AsLeafBytes;
 
-pub fn empty_hash() -> String {
-    create_leaf_hash(&0)
+/// Prefix of the leaf data in `HashMode::DomainSeparated`.
+const LEAF_PREFIX: u8 = 0x00;
+/// Prefix of the children hashes in `HashMode::DomainSeparated`.
+const NODE_PREFIX: u8 = 0x01;
+
+/// Defines how hashes of the leaves and nodes are calculated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HashMode {
+    /// Node hash is the hash of the concatenated raw bytes of its children hashes.
+    #[default]
+    Binary,
+    /// Node hash is the hash of the concatenated hex strings of its children hashes.
+    /// Reproduces root hashes calculated by previous versions of the crate only for
+    /// strings. Previous versions hashed every value as its `to_string()`, while leaves
+    /// are now hashed over `AsLeafBytes`, so roots of integers and other `Display` types
+    /// no longer match. Convert such values with `to_string()` before building the tree
+    /// to get the old roots.
+    Compat,
+    /// Leaf hash is `H(0x00 || data)` and node hash is `H(0x01 || left || right)`
+    /// as defined by RFC 6962. Leaves and nodes can't be mistaken for each other,
+    /// which protects proofs against second-preimage attacks.
+    DomainSeparated,
+}
+
+/// Defines what happens to the last node of a level which has no sibling.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OddNodePolicy {
+    /// The node is paired with itself. Trees `[1, 2, 3]` and `[1, 2, 3, 3]` have the
+    /// same root hash with this policy, it's kept for compatibility.
+    #[default]
+    Duplicate,
+    /// The node is moved to the level above unchanged, which gives the unbalanced
+    /// tree defined by RFC 6962.
+    Promote,
+    /// The node is paired with the all-zero hash, which no leaf can have, so trees
+    /// `[1, 2, 3]` and `[1, 2, 3, x]` never have the same root hash.
+    Pad,
+}
+
+/// Hash function together with the rules of combining hashes used by a tree.
+#[derive(Clone, Debug)]
+pub struct HashScheme<H: MerkleHasher> {
+    hasher: H,
+    mode: HashMode,
+    odd_node_policy: OddNodePolicy,
+    empty_root: Option<Digest>,
+}
+
+impl<H: MerkleHasher> HashScheme<H> {
+    pub fn new(hasher: H, mode: HashMode, odd_node_policy: OddNodePolicy) -> Self {
+        HashScheme {
+            hasher: hasher,
+            mode: mode,
+            odd_node_policy: odd_node_policy,
+            empty_root: None,
+        }
+    }
+
+    /// Creates scheme with `hasher` and default hashing rules.
+    pub fn with_hasher(hasher: H) -> Self {
+        Self::new(hasher, HashMode::default(), OddNodePolicy::default())
+    }
+
+    pub fn hash_mode(&self) -> HashMode {
+        self.mode
+    }
+
+    pub fn odd_node_policy(&self) -> OddNodePolicy {
+        self.odd_node_policy
+    }
+
+    /// Returns the hash the last node of a level without sibling is paired with,
+    /// or `None` if the node is promoted to the level above.
+    pub fn lone_sibling(&self, node: &Digest) -> Option<Digest> {
+        match self.odd_node_policy {
+            OddNodePolicy::Duplicate => Some(*node),
+            OddNodePolicy::Promote => None,
+            OddNodePolicy::Pad => Some(self.padding()),
+        }
+    }
+
+    /// Returns the hash of the parent of the last node of a level without sibling.
+    pub fn lone_parent(&self, node: &Digest) -> Digest {
+        match self.lone_sibling(node) {
+            Some(sibling) => self.node(node, &sibling),
+            None => *node,
+        }
+    }
+
+    /// Returns the hash the lone node is paired with in `OddNodePolicy::Pad`. It's not
+    /// a hash of any leaf, so padding can't be mistaken for a real element.
+    pub fn padding(&self) -> Digest {
+        Digest::new([0; DIGEST_SIZE])
+    }
+
+    /// Replaces the default root hash of the empty tree with `empty_root`.
+    pub fn with_empty_root(mut self, empty_root: Digest) -> Self {
+        self.empty_root = Some(empty_root);
+        self
+    }
+
+    pub fn empty_root(&self) -> Digest {
+        self.empty_root.unwrap_or_else(|| empty_hash(&self.hasher, self.mode))
+    }
+
+    pub fn leaf<T: AsLeafBytes + ?Sized>(&self, input: &T) -> Digest {
+        create_leaf_hash(&self.hasher, self.mode, input)
+    }
+
+    pub fn node(&self, left: &Digest, right: &Digest) -> Digest {
+        create_node_hash(&self.hasher, self.mode, left, right)
+    }
+}
+
+/// Returns the root hash of the empty tree: `H("")` as defined by RFC 6962 in
+/// `HashMode::DomainSeparated`, where no leaf can have it, and `H("0")` calculated by
+/// previous versions of the crate in `HashMode::Compat`. In `HashMode::Binary` `H("")`
+/// is the hash of the empty leaf, so the all-zero hash is used instead.
+pub fn empty_hash<H: MerkleHasher>(hasher: &H, mode: HashMode) -> Digest {
+    match mode {
+        HashMode::Binary => Digest::new([0; DIGEST_SIZE]),
+        HashMode::Compat => hasher.hash(&[b"0"]),
+        HashMode::DomainSeparated => hasher.hash(&[]),
+    }
 }
 
-pub fn create_leaf_hash<T: ToString>(input: &T) -> String {
-    let mut hasher = Sha256::new();
-    hasher.input_str(&input.to_string().as_ref());
-    let result = hasher.result_str();
-    result
+pub fn create_leaf_hash<H, T>(hasher: &H, mode: HashMode, input: &T) -> Digest
+    where H: MerkleHasher,
+          T: AsLeafBytes + ?Sized
+{
+    let data = input.leaf_bytes();
+    match mode {
+        HashMode::Binary | HashMode::Compat => hasher.hash(&[&data]),
+        HashMode::DomainSeparated => hasher.hash(&[&[LEAF_PREFIX], &data]),
+    }
 }
 
-pub fn create_node_hash<T: ToString>(left: &T, right: &T) -> String {
-    let mut hasher = Sha256::new();
-    hasher.input_str(left.to_string().as_ref());
-    hasher.input_str(right.to_string().as_ref());
-    let result = hasher.result_str();
-    result
+pub fn create_node_hash<H>(hasher: &H, mode: HashMode, left: &Digest, right: &Digest) -> Digest
+    where H: MerkleHasher
+{
+    match mode {
+        HashMode::Binary => hasher.hash(&[left.as_ref(), right.as_ref()]),
+        HashMode::Compat => {
+            hasher.hash(&[left.to_string().as_bytes(), right.to_string().as_bytes()])
+        }
+        HashMode::DomainSeparated => hasher.hash(&[&[NODE_PREFIX], left.as_ref(), right.as_ref()]),
+    }
 }
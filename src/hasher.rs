@@ -0,0 +1,79 @@
+use crypto::digest::Digest as CryptoDigest;
+use crypto::{blake2b, sha2, sha3};
+
+use digest::{DIGEST_SIZE, Digest};
+
+#[cfg(feature = "blake3")]
+use blake3;
+
+/// Hash function used to calculate hashes of leaves and nodes of the `MerkleTree`.
+/// The hash function must produce 256-bit digests and be shareable between threads.
+pub trait MerkleHasher: Clone + Send + Sync {
+    /// Returns hash of the concatenation of `parts`.
+    fn hash(&self, parts: &[&[u8]]) -> Digest;
+}
+
+/// SHA-256 hash function. Used by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Sha256;
+
+/// SHA-512/256 hash function.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Sha512Trunc256;
+
+/// SHA3-256 hash function.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Sha3_256;
+
+/// BLAKE2b hash function with 256-bit output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Blake2b;
+
+/// BLAKE3 hash function. Available with `blake3` feature.
+#[cfg(feature = "blake3")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Blake3;
+
+impl MerkleHasher for Sha256 {
+    fn hash(&self, parts: &[&[u8]]) -> Digest {
+        digest(sha2::Sha256::new(), parts)
+    }
+}
+
+impl MerkleHasher for Sha512Trunc256 {
+    fn hash(&self, parts: &[&[u8]]) -> Digest {
+        digest(sha2::Sha512Trunc256::new(), parts)
+    }
+}
+
+impl MerkleHasher for Sha3_256 {
+    fn hash(&self, parts: &[&[u8]]) -> Digest {
+        digest(sha3::Sha3::sha3_256(), parts)
+    }
+}
+
+impl MerkleHasher for Blake2b {
+    fn hash(&self, parts: &[&[u8]]) -> Digest {
+        digest(blake2b::Blake2b::new(DIGEST_SIZE), parts)
+    }
+}
+
+#[cfg(feature = "blake3")]
+impl MerkleHasher for Blake3 {
+    fn hash(&self, parts: &[&[u8]]) -> Digest {
+        let mut hasher = blake3::Hasher::new();
+        for part in parts {
+            hasher.update(part);
+        }
+        Digest::new(*hasher.finalize().as_bytes())
+    }
+}
+
+fn digest<D: CryptoDigest>(mut hasher: D, parts: &[&[u8]]) -> Digest {
+    for part in parts {
+        hasher.input(part);
+    }
+    let mut digest = [0; DIGEST_SIZE];
+    hasher.result(&mut digest);
+    Digest::new(digest)
+}
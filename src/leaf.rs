@@ -0,0 +1,90 @@
+use std::borrow::Cow;
+
+/// Canonical byte encoding of a value which is hashed into a leaf of the `MerkleTree`.
+///
+/// Implementations are provided for byte slices and strings (hashed as is), integers
+/// (fixed-width big-endian, `usize` and `isize` as 64-bit) and tuples (each element is
+/// prefixed with its length as 64-bit big-endian integer).
+pub trait AsLeafBytes {
+    /// Returns bytes which represent the value in the leaf hash.
+    fn leaf_bytes(&self) -> Cow<'_, [u8]>;
+}
+
+impl<T: AsLeafBytes + ?Sized> AsLeafBytes for &T {
+    fn leaf_bytes(&self) -> Cow<'_, [u8]> {
+        (**self).leaf_bytes()
+    }
+}
+
+impl AsLeafBytes for [u8] {
+    fn leaf_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl AsLeafBytes for Vec<u8> {
+    fn leaf_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl AsLeafBytes for str {
+    fn leaf_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Borrowed(self.as_bytes())
+    }
+}
+
+impl AsLeafBytes for String {
+    fn leaf_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Borrowed(self.as_bytes())
+    }
+}
+
+macro_rules! impl_as_leaf_bytes_for_int {
+    ($($t:ty),*) => {
+        $(
+            impl AsLeafBytes for $t {
+                fn leaf_bytes(&self) -> Cow<'_, [u8]> {
+                    Cow::Owned(self.to_be_bytes().to_vec())
+                }
+            }
+        )*
+    }
+}
+
+impl_as_leaf_bytes_for_int!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl AsLeafBytes for usize {
+    fn leaf_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned((*self as u64).to_be_bytes().to_vec())
+    }
+}
+
+impl AsLeafBytes for isize {
+    fn leaf_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned((*self as i64).to_be_bytes().to_vec())
+    }
+}
+
+macro_rules! impl_as_leaf_bytes_for_tuple {
+    ($($name:ident),+) => {
+        impl<$($name: AsLeafBytes),+> AsLeafBytes for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn leaf_bytes(&self) -> Cow<'_, [u8]> {
+                let ($(ref $name,)+) = *self;
+                let mut bytes = Vec::new();
+                $(append_with_length(&mut bytes, &$name.leaf_bytes());)+
+                Cow::Owned(bytes)
+            }
+        }
+    }
+}
+
+impl_as_leaf_bytes_for_tuple!(A, B);
+impl_as_leaf_bytes_for_tuple!(A, B, C);
+impl_as_leaf_bytes_for_tuple!(A, B, C, D);
+
+fn append_with_length(bytes: &mut Vec<u8>, value: &[u8]) {
+    bytes.extend_from_slice(&(value.len() as u64).to_be_bytes());
+    bytes.extend_from_slice(value);
+}
@@ -1,8 +1,8 @@
 //! merkle-tree implement Merkle Tree in Rust.
 //!
-//! A disadvantage in current implementation is need to recalculate full tree when added
-//! or removed element into/from tree. To increase performance we need to implement partial
-//! tree modification when adding or removing element.
+//! Adding or removing elements recalculates only the nodes which depend on the changed
+//! leaves: pushing or popping an element touches a single path from the leaf to the root,
+//! removing an element from the middle recalculates the part of each level to the right of it.
 
 #![deny(missing_docs,
 missing_debug_implementations,
@@ -13,16 +13,40 @@ unsafe_code,
 unused_import_braces,
 unused_qualifications)]
 #![feature(test)]
-#![feature(step_by)]
 
 extern crate crypto;
 extern crate test;
+#[cfg(feature = "blake3")]
+extern crate blake3;
+#[cfg(feature = "parallel")]
+extern crate rayon;
 
-mod element;
+mod builder;
+mod consistency;
+mod digest;
 mod hash_utils;
+mod hasher;
+mod leaf;
 mod merkle_tree;
+mod multiproof;
 mod proof;
+mod range_proof;
+mod root_builder;
+mod sorted_tree;
 
 mod tests;
 
-pub use self::merkle_tree::MerkleTree;
+pub use self::builder::MerkleTreeBuilder;
+pub use self::consistency::ConsistencyProof;
+pub use self::digest::{DIGEST_SIZE, Digest, ParseDigestError};
+pub use self::hash_utils::{HashMode, OddNodePolicy};
+pub use self::hasher::{Blake2b, MerkleHasher, Sha256, Sha3_256, Sha512Trunc256};
+#[cfg(feature = "blake3")]
+pub use self::hasher::Blake3;
+pub use self::leaf::AsLeafBytes;
+pub use self::merkle_tree::{MerkleTree, ProofNode};
+pub use self::multiproof::MultiProof;
+pub use self::proof::{Proof, ProofError, VerifyError};
+pub use self::range_proof::RangeProof;
+pub use self::root_builder::MerkleRootBuilder;
+pub use self::sorted_tree::{AbsenceProof, SortedMerkleTree};
@@ -1,29 +1,50 @@
-use std::collections::{BTreeMap, VecDeque};
+use std::collections::VecDeque;
 use std::collections::vec_deque::Iter;
-use std::fmt::Display;
-use std::rc::Rc;
+use std::mem;
 
-use element::Element;
-use hash_utils::*;
-use proof::Proof;
+use consistency::ConsistencyProof;
+use digest::Digest;
+use hash_utils::{HashScheme, OddNodePolicy};
+use hasher::{MerkleHasher, Sha256};
+use leaf::AsLeafBytes;
+use multiproof::MultiProof;
+use proof::{Proof, ProofError};
+use range_proof::RangeProof;
 
-#[derive(Debug)]
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Minimal number of hashes which are calculated by the thread pool, smaller
+/// batches (e.g. a single pushed leaf) are hashed on the current thread.
+#[cfg(feature = "parallel")]
+const PARALLEL_THRESHOLD: usize = 1024;
+/// Number of leaves encoded at once before they are hashed by the thread pool.
+#[cfg(feature = "parallel")]
+const PARALLEL_CHUNK_SIZE: usize = 1 << 16;
+
+/// Hash of a sibling node on the path from a leaf to the root.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ProofNode {
-    Left(String),
-    Right(String),
+    /// Sibling node is placed to the left of the path.
+    Left(Digest),
+    /// Sibling node is placed to the right of the path.
+    Right(Digest),
 }
 
-/// MerkleTree struct represents merkle binary tree with values of type `T` and map of nodes.
+/// MerkleTree struct represents merkle binary tree with values of type `T` and hashes of
+/// its nodes stored level by level.
+/// Hashes are calculated with the hash function `H`, which is SHA-256 by default.
 #[derive(Debug)]
-pub struct MerkleTree<T: ToString + Display + Clone> {
-    root: Element<T>,
+pub struct MerkleTree<T: AsLeafBytes + Clone, H: MerkleHasher = Sha256> {
+    root: Digest,
     height: usize,
     count: usize,
-    storage: VecDeque<Rc<T>>,
-    nodes: BTreeMap<usize, VecDeque<Element<T>>>,
+    storage: VecDeque<T>,
+    levels: Vec<Vec<Digest>>,
+    scheme: HashScheme<H>,
 }
 
-impl<T: ToString + Display + Clone> MerkleTree<T> {
+impl<T: AsLeafBytes + Clone> MerkleTree<T> {
     /// Creates new, empty `MerkleTree`.
     /// # Examples
     ///
@@ -31,17 +52,11 @@ impl<T: ToString + Display + Clone> MerkleTree<T> {
     /// extern crate merkle_tree;
     /// use merkle_tree::MerkleTree;
     ///
-    /// let tree = MerkleTree::new();
+    /// let tree: MerkleTree<u32> = MerkleTree::new();
     /// assert_eq!(0, tree.len());
     /// ```
     pub fn new() -> Self {
-        MerkleTree {
-            root: Element::empty(),
-            height: 0,
-            count: 0,
-            storage: VecDeque::new(),
-            nodes: BTreeMap::new(),
-        }
+        Self::with_hasher(Sha256)
     }
 
     /// Creates `MerkleTree` from `Vec` of elements.
@@ -56,18 +71,63 @@ impl<T: ToString + Display + Clone> MerkleTree<T> {
     /// assert_eq!(4, tree.len());
     /// ```
     pub fn from_vec(data: Vec<T>) -> Self {
+        Self::from_vec_with_hasher(data, Sha256)
+    }
+}
+
+impl<T: AsLeafBytes + Clone, H: MerkleHasher> MerkleTree<T, H> {
+    /// Creates new, empty `MerkleTree` which uses `hasher` to calculate hashes.
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate merkle_tree;
+    /// use merkle_tree::{Blake2b, MerkleTree};
+    ///
+    /// let mut tree = MerkleTree::with_hasher(Blake2b);
+    /// tree.push(1);
+    /// assert_eq!(1, tree.len());
+    /// ```
+    pub fn with_hasher(hasher: H) -> Self {
+        Self::with_scheme(HashScheme::with_hasher(hasher))
+    }
+
+    /// Creates `MerkleTree` from `Vec` of elements which uses `hasher` to calculate hashes.
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate merkle_tree;
+    /// use merkle_tree::{MerkleTree, Sha3_256};
+    ///
+    /// let tree = MerkleTree::from_vec_with_hasher(vec![1, 2, 3, 4], Sha3_256);
+    /// assert_eq!(4, tree.len());
+    /// ```
+    pub fn from_vec_with_hasher(data: Vec<T>, hasher: H) -> Self {
+        Self::from_vec_with_scheme(data, HashScheme::with_hasher(hasher))
+    }
+
+    pub(crate) fn with_scheme(scheme: HashScheme<H>) -> Self {
+        MerkleTree {
+            root: scheme.empty_root(),
+            height: 0,
+            count: 0,
+            storage: VecDeque::new(),
+            levels: Vec::new(),
+            scheme: scheme,
+        }
+    }
+
+    pub(crate) fn from_vec_with_scheme(data: Vec<T>, scheme: HashScheme<H>) -> Self {
         if data.is_empty() {
-            Self::new()
+            Self::with_scheme(scheme)
         } else {
-            let elements = data.into_iter()
-                .map(|e| Rc::new(e))
-                .collect::<VecDeque<Rc<T>>>();
+            let elements = data.into_iter().collect::<VecDeque<T>>();
             let mut result = MerkleTree {
-                root: Element::empty(),
+                root: scheme.empty_root(),
                 height: 0,
                 count: 0,
                 storage: elements,
-                nodes: BTreeMap::new(),
+                levels: Vec::new(),
+                scheme: scheme,
             };
             result.calculate_tree();
             result
@@ -86,9 +146,65 @@ impl<T: ToString + Display + Clone> MerkleTree<T> {
     /// assert_eq!(1, tree.len());
     /// ```
     pub fn push(&mut self, value: T) {
-        self.storage.push_back(Rc::new(value));
-        self.count = self.storage.len();
-        self.calculate_tree();
+        self.storage.push_back(value);
+        let index = self.storage.len() - 1;
+        self.recalculate_from(index);
+    }
+
+    /// Appends all elements of `values` to the end of the tree. The tree is
+    /// recalculated once for the whole batch, which gives the same root hash as
+    /// pushing the elements one by one.
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate merkle_tree;
+    /// use merkle_tree::MerkleTree;
+    ///
+    /// let mut tree = MerkleTree::from_vec(vec![1, 2]);
+    /// tree.append(vec![3, 4]);
+    /// assert_eq!(4, tree.len());
+    /// ```
+    pub fn append(&mut self, values: Vec<T>) {
+        self.extend(values);
+    }
+
+    /// Clones and appends all elements of `values` to the end of the tree.
+    /// The tree is recalculated once for the whole batch.
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate merkle_tree;
+    /// use merkle_tree::MerkleTree;
+    ///
+    /// let mut tree = MerkleTree::new();
+    /// tree.extend_from_slice(&[1, 2, 3]);
+    /// assert_eq!(3, tree.len());
+    /// ```
+    pub fn extend_from_slice(&mut self, values: &[T]) {
+        self.extend(values.iter().cloned());
+    }
+
+    /// Inserts an element at position `index` within the tree, shifting all
+    /// elements after it to the right. Only the nodes to the right of the
+    /// inserted leaf are recalculated on each level.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than tree's length.
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate merkle_tree;
+    /// use merkle_tree::MerkleTree;
+    ///
+    /// let mut tree = MerkleTree::from_vec(vec![1, 2, 3]);
+    /// tree.insert(1, 4);
+    /// assert_eq!(tree.get_values(), Some(vec![1, 4, 2, 3]));
+    /// ```
+    pub fn insert(&mut self, index: usize, value: T) {
+        assert!(index <= self.storage.len(), "index out of bounds");
+        self.storage.insert(index, value);
+        self.recalculate_from(index);
     }
 
     /// Removes element from the tree and returns `true` if element was removed
@@ -106,14 +222,59 @@ impl<T: ToString + Display + Clone> MerkleTree<T> {
     /// ```
     pub fn remove(&mut self, index: usize) -> bool {
         if let Some(_) = self.storage.remove(index) {
-            self.count = self.storage.len();
-            self.calculate_tree();
+            self.recalculate_from(index);
             true
         } else {
             false
         }
     }
 
+    /// Removes the last element from the tree and returns it, or `None` if the tree is empty.
+    /// Only the right-most path from the removed leaf to the root is recalculated.
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate merkle_tree;
+    /// use merkle_tree::MerkleTree;
+    ///
+    /// let mut tree = MerkleTree::from_vec(vec![1, 2, 3]);
+    /// assert_eq!(Some(3), tree.pop());
+    /// assert_eq!(2, tree.len());
+    /// ```
+    pub fn pop(&mut self) -> Option<T> {
+        let value = self.storage.pop_back();
+        if value.is_some() {
+            let index = self.storage.len();
+            self.recalculate_from(index);
+        }
+        value
+    }
+
+    /// Replaces the element at `index` with `value` and returns the old element,
+    /// or `None` if `index` out of bounds. Only the path from the changed leaf
+    /// to the root is recalculated.
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate merkle_tree;
+    /// use merkle_tree::MerkleTree;
+    ///
+    /// let mut tree = MerkleTree::from_vec(vec![1, 2, 3]);
+    /// assert_eq!(Some(2), tree.set(1, 5));
+    /// assert_eq!(Some(&5), tree.get(1));
+    /// assert_eq!(None, tree.set(3, 4));
+    /// ```
+    pub fn set(&mut self, index: usize, value: T) -> Option<T> {
+        if index >= self.storage.len() {
+            return None;
+        }
+
+        self.levels[0][index] = self.scheme.leaf(&value);
+        let old_value = mem::replace(&mut self.storage[index], value);
+        self.recalculate_path(index);
+        Some(old_value)
+    }
+
     /// Retrieves an element in the `MerkleTree` by index.
     /// # Examples
     ///
@@ -127,11 +288,7 @@ impl<T: ToString + Display + Clone> MerkleTree<T> {
     /// tree.push(3);
     /// assert_eq!(tree.get(1), Some(&2));
     pub fn get(&self, index: usize) -> Option<&T> {
-        if let Some(v) = self.storage.get(index) {
-            Some(v.as_ref())
-        } else {
-            None
-        }
+        self.storage.get(index)
     }
 
     /// Retrieves copies of all elements in the `MerkleTree`.
@@ -152,7 +309,7 @@ impl<T: ToString + Display + Clone> MerkleTree<T> {
         } else {
             let values = self.storage
                 .iter()
-                .map(|v| v.as_ref().clone())
+                .cloned()
                 .collect::<Vec<T>>();
             Some(values)
         }
@@ -212,7 +369,9 @@ impl<T: ToString + Display + Clone> MerkleTree<T> {
     }
 
 
-    /// Returns root hash of `MerkleTree`
+    /// Returns root hash of `MerkleTree`. The root hash of the empty tree is the all-zero
+    /// hash unless another one was set by `MerkleTreeBuilder::empty_root`, in
+    /// `HashMode::DomainSeparated` it's `H("")` and in `HashMode::Compat` it's `H("0")`.
     /// # Examples
     ///
     /// ```
@@ -220,15 +379,17 @@ impl<T: ToString + Display + Clone> MerkleTree<T> {
     /// use merkle_tree::MerkleTree;
     ///
     /// let mut tree = MerkleTree::new();
-    /// assert_eq!(Some(&"5feceb66ffc86f38d952786c6d696c79c2dbc239dd4e91b46729d73a27fb57e9".to_string()), tree.root_hash());
+    /// assert_eq!("0000000000000000000000000000000000000000000000000000000000000000",
+    ///            tree.root_hash().unwrap().to_string());
     /// tree.push(1);
     /// tree.push(2);
     /// tree.push(3);
     /// tree.push(4);
-    /// assert_eq!(Some(&"85df8945419d2b5038f7ac83ec1ec6b8267c40fdb3b1e56ff62f6676eb855e70".to_string()), tree.root_hash());
+    /// assert_eq!("327cf213e1738de4206bfd14297c26c682961750cb56897ed5e8f519b0548ff2",
+    ///            tree.root_hash().unwrap().to_string());
     /// ```
-    pub fn root_hash(&self) -> Option<&String> {
-        self.root.hash()
+    pub fn root_hash(&self) -> Option<&Digest> {
+        Some(&self.root)
     }
 
     /// Returns a front-to-back iterator.
@@ -239,119 +400,482 @@ impl<T: ToString + Display + Clone> MerkleTree<T> {
     /// use merkle_tree::MerkleTree;
     ///
     /// let tree = MerkleTree::from_vec(vec![1, 2, 3, 4]);
-    /// let vec: Vec<Rc<i32>> = tree.iter().collect();
+    /// let vec: Vec<&i32> = tree.iter().collect();
     /// assert_eq!(4, vec.len());
     /// ```
-    pub fn iter(&self) -> Iter<Rc<T>> {
+    pub fn iter(&self) -> Iter<'_, T> {
         self.storage.iter()
     }
 
-    /// Returns the proof for checking if `value` really in tree.
+    /// Returns the proof for checking if `value` really in tree, or an error if
+    /// the tree is empty or doesn't contain `value`.
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate merkle_tree;
+    /// use merkle_tree::{MerkleTree, ProofError};
+    ///
+    /// let tree = MerkleTree::from_vec(vec![1, 2, 3, 4]);
+    /// let proof = tree.get_proof(3).unwrap();
+    /// assert!(proof.validate(tree.root_hash().unwrap()));
+    /// assert_eq!(Some(ProofError::ValueNotPresent), tree.get_proof(5).err());
+    /// ```
+    pub fn get_proof(&self, value: T) -> Result<Proof<T, H>, ProofError> {
+        if self.is_empty() {
+            return Err(ProofError::EmptyTree);
+        }
+
+        match self.leaf_indices(&value).first() {
+            Some(&index) => self.get_proof_at(index),
+            None => Err(ProofError::ValueNotPresent),
+        }
+    }
+
+    /// Returns proofs for every occurrence of `value` in the tree together
+    /// with the index of the leaf each proof was built for.
     /// # Examples
     ///
     /// ```
     /// extern crate merkle_tree;
     /// use merkle_tree::MerkleTree;
     ///
+    /// let tree = MerkleTree::from_vec(vec![5, 7, 5, 9]);
+    /// let proofs = tree.get_proofs_for(&5);
+    /// assert_eq!(vec![0, 2], proofs.iter().map(|p| p.0).collect::<Vec<_>>());
+    /// ```
+    pub fn get_proofs_for(&self, value: &T) -> Vec<(usize, Proof<T, H>)> {
+        self.leaf_indices(value)
+            .into_iter()
+            .filter_map(|index| self.get_proof_at(index).ok().map(|proof| (index, proof)))
+            .collect()
+    }
+
+    /// Returns the proof for checking if the element at `index` really in tree,
+    /// or an error if the tree is empty or `index` out of bounds. The path is
+    /// collected from sibling positions, so it takes `O(log n)` and works for
+    /// duplicated values.
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate merkle_tree;
+    /// use merkle_tree::{MerkleTree, ProofError};
+    ///
     /// let tree = MerkleTree::from_vec(vec![1, 2, 3, 4]);
-    /// let proof = tree.get_proof(3);
-    /// assert!(proof.validate(tree.root_hash().unwrap());
+    /// let proof = tree.get_proof_at(2).unwrap();
+    /// assert!(proof.validate(tree.root_hash().unwrap()));
+    /// assert_eq!(Some(ProofError::IndexOutOfRange), tree.get_proof_at(4).err());
+    /// ```
+    pub fn get_proof_at(&self, index: usize) -> Result<Proof<T, H>, ProofError> {
+        self.get_proof_at_size(index, self.count)
+    }
+
+    /// Returns the proof for checking if the element at `index` is in the tree of the
+    /// current first `tree_size` elements. If the tree was only appended to since it had
+    /// `tree_size` elements, the proof is valid against the root hash published then.
+    /// After `set`, `insert` or `remove` of any of those elements it isn't. Returns an
+    /// error if the tree is empty or `index` is out of bounds of that tree.
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate merkle_tree;
+    /// use merkle_tree::MerkleTree;
+    ///
+    /// let mut tree = MerkleTree::from_vec(vec![1, 2, 3]);
+    /// let old_root = *tree.root_hash().unwrap();
+    /// tree.append(vec![4, 5]);
+    /// let proof = tree.get_proof_at_size(1, 3).unwrap();
+    /// assert!(proof.verify_against(&old_root).is_ok());
+    /// ```
+    pub fn get_proof_at_size(&self,
+                             index: usize,
+                             tree_size: usize)
+                             -> Result<Proof<T, H>, ProofError> {
+        if self.is_empty() {
+            return Err(ProofError::EmptyTree);
+        }
+        if index >= tree_size || tree_size > self.count {
+            return Err(ProofError::IndexOutOfRange);
+        }
+
+        let edges = self.prefix_edges(tree_size);
+        let height = edges.len() - 1;
+        let mut path = Vec::new();
+        let mut position = index;
+        for (level, edge) in edges.iter().enumerate().take(height) {
+            let last = (tree_size - 1) >> level;
+            if position % 2 == 1 {
+                path.push(ProofNode::Left(self.levels[level][position - 1]));
+            } else if position + 1 < last {
+                path.push(ProofNode::Right(self.levels[level][position + 1]));
+            } else if position + 1 == last {
+                path.push(ProofNode::Right(*edge));
+            } else if let Some(sibling) = self.scheme.lone_sibling(edge) {
+                path.push(ProofNode::Right(sibling));
+            }
+            position /= 2;
+        }
+        Ok(Proof::with_scheme(edges[height],
+                              self.storage[index].clone(),
+                              index,
+                              tree_size,
+                              path,
+                              self.scheme.clone()))
+    }
+
+    /// Returns the proof for checking if the elements at all `indices` really in tree,
+    /// or an error if the tree is empty, `indices` is empty or any index out of bounds.
+    /// Duplicated indices are proven once.
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate merkle_tree;
+    /// use merkle_tree::MerkleTree;
+    ///
+    /// let tree = MerkleTree::from_vec(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    /// let proof = tree.get_multiproof(&[0, 1, 3]).unwrap();
+    /// assert_eq!(2, proof.hashes().len());
+    /// assert!(proof.validate(tree.root_hash().unwrap()));
+    /// ```
+    pub fn get_multiproof(&self, indices: &[usize]) -> Result<MultiProof<T, H>, ProofError> {
+        if self.is_empty() {
+            return Err(ProofError::EmptyTree);
+        }
+        let mut indices = indices.to_vec();
+        indices.sort();
+        indices.dedup();
+        match indices.last() {
+            Some(&index) if index < self.count => {}
+            _ => return Err(ProofError::IndexOutOfRange),
+        }
+
+        let values = indices.iter().map(|&index| (index, self.storage[index].clone())).collect();
+        let hashes = self.sibling_hashes(indices);
+        Ok(MultiProof::with_scheme(self.root, values, self.count, hashes, self.scheme.clone()))
+    }
+
+    /// Returns the proof that `values` are exactly the elements from `start` to `end`
+    /// (exclusive) of the tree, or an error if the tree is empty or the range is empty
+    /// or out of bounds.
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate merkle_tree;
+    /// use merkle_tree::MerkleTree;
+    ///
+    /// let tree = MerkleTree::from_vec(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    /// let proof = tree.get_range_proof(2, 5).unwrap();
+    /// assert!(proof.validate(tree.root_hash().unwrap(), tree.len(), 2, &[3, 4, 5]));
+    /// assert!(!proof.validate(tree.root_hash().unwrap(), tree.len(), 2, &[3, 5, 4]));
+    /// ```
+    pub fn get_range_proof(&self, start: usize, end: usize) -> Result<RangeProof<H>, ProofError> {
+        if self.is_empty() {
+            return Err(ProofError::EmptyTree);
+        }
+        if start >= end || end > self.count {
+            return Err(ProofError::IndexOutOfRange);
+        }
+
+        let hashes = self.sibling_hashes((start..end).collect());
+        Ok(RangeProof::with_scheme(start, end, self.count, hashes, self.scheme.clone()))
+    }
+
+    /// Returns the root hash of the tree of the current first `tree_size` elements, or
+    /// `None` if `tree_size` is greater than the length of the tree. It's the root hash
+    /// the tree had when it contained `tree_size` elements only if the tree was appended
+    /// to since then, `set`, `insert` or `remove` of any of those elements changes it.
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate merkle_tree;
+    /// use merkle_tree::MerkleTree;
+    ///
+    /// let mut tree = MerkleTree::from_vec(vec![1, 2, 3]);
+    /// let old_root = *tree.root_hash().unwrap();
+    /// tree.push(4);
+    /// assert_eq!(Some(old_root), tree.root_at_size(3));
+    /// assert_eq!(None, tree.root_at_size(5));
+    /// ```
+    pub fn root_at_size(&self, tree_size: usize) -> Option<Digest> {
+        if tree_size > self.count {
+            None
+        } else if tree_size == 0 {
+            Some(self.scheme.empty_root())
+        } else {
+            self.prefix_edges(tree_size).last().cloned()
+        }
+    }
+
+    /// Returns the proof that the tree of the first `old_size` elements is a prefix of
+    /// the tree of the first `new_size` elements, as defined by RFC 6962. Requires
+    /// `OddNodePolicy::Promote`, which gives the tree structure of RFC 6962.
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate merkle_tree;
+    /// use merkle_tree::{MerkleTreeBuilder, OddNodePolicy, ProofError};
+    ///
+    /// let tree = MerkleTreeBuilder::new()
+    ///     .odd_node_policy(OddNodePolicy::Promote)
+    ///     .build_from_vec(vec![1, 2, 3, 4, 5]);
+    /// assert_eq!(2, tree.get_consistency_proof(2, 5).unwrap().path().len());
+    /// assert_eq!(Some(ProofError::IndexOutOfRange),
+    ///            tree.get_consistency_proof(2, 6).err());
     /// ```
-    pub fn get_proof(&self, value: T) -> Proof<T> {
-        let path = self.get_needed_hashes_for_proof(&value);
-        Proof::new(self.root_hash().unwrap().clone(), value.clone(), path)
+    pub fn get_consistency_proof(&self,
+                                 old_size: usize,
+                                 new_size: usize)
+                                 -> Result<ConsistencyProof<H>, ProofError> {
+        if self.is_empty() {
+            return Err(ProofError::EmptyTree);
+        }
+        if old_size == 0 || old_size > new_size || new_size > self.count {
+            return Err(ProofError::IndexOutOfRange);
+        }
+        if self.scheme.odd_node_policy() != OddNodePolicy::Promote {
+            return Err(ProofError::UnsupportedPolicy);
+        }
+
+        // SUBPROOF of RFC 6962, section 2.1.2, collected from the root down.
+        let mut path = Vec::new();
+        let (mut start, mut end) = (0, new_size);
+        let mut remaining = old_size;
+        let mut complete = true;
+        while remaining < end - start {
+            let split = (end - start).next_power_of_two() / 2;
+            if remaining <= split {
+                path.push(self.subtree_hash(start + split, end));
+                end = start + split;
+            } else {
+                path.push(self.subtree_hash(start, start + split));
+                start += split;
+                remaining -= split;
+                complete = false;
+            }
+        }
+        if !complete {
+            path.push(self.subtree_hash(start, end));
+        }
+        path.reverse();
+
+        Ok(ConsistencyProof::with_scheme(old_size,
+                                         new_size,
+                                         path,
+                                         self.scheme.clone()))
     }
 
     fn calculate_tree(&mut self) {
+        self.levels.clear();
+        self.recalculate_from(0);
+    }
+
+    /// Recalculates only the nodes which depend on leaves starting from `index`.
+    /// Nodes to the left of that path are kept as is, so appending or popping a leaf
+    /// costs `O(log n)` hashes instead of rebuilding the whole tree.
+    fn recalculate_from(&mut self, index: usize) {
         self.count = self.storage.len();
         self.height = calculate_height(self.count);
-        self.root = Element::empty();
-        self.nodes.clear();
-        let mut current_level = self.height;
-
-        if !self.storage.is_empty() {
-            let mut leaves = VecDeque::new();
-            for value in &self.storage {
-                let e = Element::create_leaf(value.clone());
-                leaves.push_back(e);
-            }
 
-            self.nodes.insert(current_level, leaves);
-
-            while current_level > 0 {
-                let above_level = current_level - 1;
-                let above_row = {
-                    let mut row = VecDeque::new();
-                    let current_row = self.nodes.get(&current_level).unwrap();
-                    for i in (0..current_row.len()).step_by(2) {
-                        let left = current_row.get(i).unwrap();
-                        let right = current_row.get(i + 1).unwrap_or(left);
-                        let node = Element::create_node(left.clone(), right.clone());
-                        row.push_back(node);
-                    }
-                    row
-                };
-
-                self.nodes.insert(above_level, above_row);
-                current_level -= 1;
+        if self.storage.is_empty() {
+            self.root = self.scheme.empty_root();
+            self.levels.clear();
+            return;
+        }
+
+        // Levels are stored from the leaves up, so a level keeps its hashes
+        // when the height of the tree changes.
+        self.levels.resize(self.height + 1, Vec::new());
+        {
+            let leaves = &mut self.levels[0];
+            leaves.truncate(index);
+            hash_leaves(leaves, self.storage.iter().skip(index), &self.scheme);
+        }
+
+        let mut index = index;
+        for level in 1..self.height + 1 {
+            index /= 2;
+            let (below, above) = self.levels.split_at_mut(level);
+            let current_row = &below[level - 1];
+            let above_row = &mut above[0];
+            above_row.truncate(index);
+            hash_parents(above_row, current_row, index, &self.scheme);
+        }
+        self.root = self.levels[self.height][0];
+    }
+
+    /// Recalculates the nodes on the path from the leaf at `index` to the root.
+    fn recalculate_path(&mut self, index: usize) {
+        let mut index = index;
+        for level in 1..self.height + 1 {
+            index /= 2;
+            let node = create_parent(&self.levels[level - 1], index, &self.scheme);
+            self.levels[level][index] = node;
+        }
+        self.root = self.levels[self.height][0];
+    }
+
+    /// Returns the hash of the subtree over leaves from `start` to `end`, which is either
+    /// a stored node or is split as defined by RFC 6962 for `OddNodePolicy::Promote`.
+    fn subtree_hash(&self, start: usize, end: usize) -> Digest {
+        let width = (end - start).next_power_of_two();
+        let level = width.trailing_zeros() as usize;
+        if end - start == width || end == self.count {
+            self.levels[level][start >> level]
+        } else {
+            let split = start + width / 2;
+            self.scheme.node(&self.subtree_hash(start, split), &self.subtree_hash(split, end))
+        }
+    }
+
+    /// Returns sibling hashes required to calculate the root hash from the leaves
+    /// at sorted and unique `indices`, level by level from the leaves up.
+    fn sibling_hashes(&self, indices: Vec<usize>) -> Vec<Digest> {
+        let mut indices = indices;
+        let mut hashes = Vec::new();
+        for row in &self.levels[..self.height] {
+            let mut parents = Vec::with_capacity(indices.len());
+            let mut i = 0;
+            while i < indices.len() {
+                let index = indices[i];
+                if index % 2 == 1 {
+                    hashes.push(row[index - 1]);
+                } else if i + 1 < indices.len() && indices[i + 1] == index + 1 {
+                    // The sibling is calculated from the proven leaves.
+                    i += 1;
+                } else if let Some(sibling) = row.get(index + 1) {
+                    hashes.push(*sibling);
+                }
+                parents.push(index / 2);
+                i += 1;
             }
-            assert!(current_level == 0);
-            self.root = self.nodes.get(&0).unwrap()[0].clone(); //root_node;
-        }
-    }
-
-    fn get_needed_hashes_for_proof(&self, value: &T) -> Vec<ProofNode> {
-        let mut level = self.height;
-        let mut next_hash = create_leaf_hash(&value);
-        let mut needed_hashes = Vec::new();
-
-        while level > 0 {
-            if let Some(index) = self.get_element_index(level, &next_hash) {
-                let nodes = self.nodes.get(&level).unwrap();
-                match nodes.get(index) {
-                    Some(&Element::Leaf { ref hash, .. }) |
-                    Some(&Element::Node { ref hash, .. }) => {
-                        if index % 2 == 0 {
-                            if let Some(sibling_node) = nodes.get(index + 1) {
-                                needed_hashes.push(ProofNode::Right(sibling_node
-                                                                        .hash()
-                                                                        .unwrap()
-                                                                        .clone()));
-                                next_hash = create_node_hash(hash, sibling_node.hash().unwrap());
-                            } else {
-                                needed_hashes.push(ProofNode::Right(hash.clone()));
-                                next_hash = create_node_hash(hash, hash);
-                            }
-                        } else {
-                            if let Some(sibling_node) = nodes.get(index - 1) {
-                                needed_hashes.push(ProofNode::Left(sibling_node
-                                                                       .hash()
-                                                                       .unwrap()
-                                                                       .clone()));
-                                next_hash = create_node_hash(sibling_node.hash().unwrap(), hash);
-                            }
-                        }
-                    }
-                    _ => continue,
-                };
+            indices = parents;
+        }
+        hashes
+    }
+
+    /// Returns hashes of the last node of each level of the tree which contained the
+    /// first `tree_size` elements, from the leaves up to the root. Other nodes of that
+    /// tree cover the same leaves in the current tree, so they are taken from it.
+    fn prefix_edges(&self, tree_size: usize) -> Vec<Digest> {
+        let height = calculate_height(tree_size);
+        let mut edges: Vec<Digest> = Vec::with_capacity(height + 1);
+        for level in 0..height + 1 {
+            let last = (tree_size - 1) >> level;
+            let edge = if tree_size == self.count || tree_size.is_multiple_of(1 << level) {
+                self.levels[level][last]
+            } else {
+                let below = &edges[level - 1];
+                let below_last = (tree_size - 1) >> (level - 1);
+                if below_last % 2 == 1 {
+                    self.scheme.node(&self.levels[level - 1][below_last - 1], below)
+                } else {
+                    self.scheme.lone_parent(below)
+                }
+            };
+            edges.push(edge);
+        }
+        edges
+    }
+
+    /// Returns indices of all leaves which hash of `value` belongs to.
+    fn leaf_indices(&self, value: &T) -> Vec<usize> {
+        let hash = self.scheme.leaf(value);
+        match self.levels.first() {
+            Some(leaves) => {
+                leaves.iter()
+                    .enumerate()
+                    .filter(|&(_, leaf)| *leaf == hash)
+                    .map(|(index, _)| index)
+                    .collect()
             }
-            level -= 1;
+            None => Vec::new(),
+        }
+    }
+}
+
+
+impl<T: AsLeafBytes + Clone, H: MerkleHasher> Extend<T> for MerkleTree<T, H> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let index = self.storage.len();
+        self.storage.extend(iter);
+        if self.storage.len() > index {
+            self.recalculate_from(index);
         }
-        needed_hashes
     }
+}
+
+/// Appends hashes of `values` to `leaves`.
+#[cfg(not(feature = "parallel"))]
+fn hash_leaves<'a, T, H, I>(leaves: &mut Vec<Digest>, values: I, scheme: &HashScheme<H>)
+    where T: AsLeafBytes + 'a,
+          H: MerkleHasher,
+          I: Iterator<Item = &'a T>
+{
+    leaves.extend(values.map(|value| scheme.leaf(value)));
+}
 
-    fn get_element_index(&self, level: usize, hash: &String) -> Option<usize> {
-        let row_hashes = self.nodes
-            .get(&level)
-            .unwrap()
-            .iter()
-            .map(|e| e.hash().unwrap())
-            .collect::<Vec<&String>>();
-        row_hashes.iter().position(|&s| s == hash)
+/// Appends hashes of `values` to `leaves`. Values are encoded on the current thread
+/// in chunks, so `T` doesn't have to be `Sync`, and the encoded chunks are hashed
+/// by the thread pool.
+#[cfg(feature = "parallel")]
+fn hash_leaves<'a, T, H, I>(leaves: &mut Vec<Digest>, values: I, scheme: &HashScheme<H>)
+    where T: AsLeafBytes + 'a,
+          H: MerkleHasher,
+          I: Iterator<Item = &'a T>
+{
+    let mut values = values;
+    loop {
+        let chunk = values.by_ref()
+            .take(PARALLEL_CHUNK_SIZE)
+            .map(|value| value.leaf_bytes())
+            .collect::<Vec<_>>();
+        if chunk.len() < PARALLEL_THRESHOLD {
+            leaves.extend(chunk.iter().map(|bytes| scheme.leaf(&**bytes)));
+        } else {
+            leaves.par_extend(chunk.par_iter().map(|bytes| scheme.leaf(&**bytes)));
+        }
+        if chunk.len() < PARALLEL_CHUNK_SIZE {
+            break;
+        }
     }
 }
 
+/// Appends hashes of the nodes starting from `index` of the level above `row` to `above_row`.
+#[cfg(not(feature = "parallel"))]
+fn hash_parents<H>(above_row: &mut Vec<Digest>,
+                   row: &[Digest],
+                   index: usize,
+                   scheme: &HashScheme<H>)
+    where H: MerkleHasher
+{
+    above_row.extend((index..row.len().div_ceil(2)).map(|i| create_parent(row, i, scheme)));
+}
 
+/// Appends hashes of the nodes starting from `index` of the level above `row` to `above_row`.
+/// Large levels are hashed by the thread pool.
+#[cfg(feature = "parallel")]
+fn hash_parents<H>(above_row: &mut Vec<Digest>,
+                   row: &[Digest],
+                   index: usize,
+                   scheme: &HashScheme<H>)
+    where H: MerkleHasher
+{
+    let parents = index..row.len().div_ceil(2);
+    if parents.len() < PARALLEL_THRESHOLD {
+        above_row.extend(parents.map(|i| create_parent(row, i, scheme)));
+    } else {
+        above_row.par_extend(parents.into_par_iter().map(|i| create_parent(row, i, scheme)));
+    }
+}
+
+/// Calculates the hash of the node at `index` of the level above `row`. The last node
+/// of the row without sibling is handled according to the `OddNodePolicy` of the scheme.
+fn create_parent<H: MerkleHasher>(row: &[Digest], index: usize, scheme: &HashScheme<H>) -> Digest {
+    let left = &row[index * 2];
+    match row.get(index * 2 + 1) {
+        Some(right) => scheme.node(left, right),
+        None => scheme.lone_parent(left),
+    }
+}
 
 pub fn calculate_height(count: usize) -> usize {
     if count > 0 {
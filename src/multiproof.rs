@@ -0,0 +1,117 @@
+use digest::Digest;
+use hash_utils::HashScheme;
+use hasher::{MerkleHasher, Sha256};
+use leaf::AsLeafBytes;
+
+/// Proof that several values are leaves of the `MerkleTree` with a particular root hash.
+/// Sibling hashes shared by paths of different leaves, or calculated from the leaves
+/// themselves, are included only once. Built by `MerkleTree::get_multiproof`.
+#[derive(Debug)]
+pub struct MultiProof<T: AsLeafBytes, H: MerkleHasher = Sha256> {
+    root_hash: Digest,
+    values: Vec<(usize, T)>,
+    tree_size: usize,
+    hashes: Vec<Digest>,
+    scheme: HashScheme<H>,
+}
+
+impl<T, H> MultiProof<T, H>
+    where T: AsLeafBytes,
+          H: MerkleHasher
+{
+    pub(crate) fn with_scheme(root_hash: Digest,
+                              values: Vec<(usize, T)>,
+                              tree_size: usize,
+                              hashes: Vec<Digest>,
+                              scheme: HashScheme<H>)
+                              -> Self {
+        MultiProof {
+            root_hash: root_hash,
+            values: values,
+            tree_size: tree_size,
+            hashes: hashes,
+            scheme: scheme,
+        }
+    }
+
+    /// Returns the root hash the proof claims to lead to.
+    pub fn root_hash(&self) -> &Digest {
+        &self.root_hash
+    }
+
+    /// Returns the proven values together with their indices in ascending order.
+    pub fn values(&self) -> &[(usize, T)] {
+        &self.values
+    }
+
+    /// Returns the number of elements in the tree the proof was built for.
+    pub fn tree_size(&self) -> usize {
+        self.tree_size
+    }
+
+    /// Returns sibling hashes required to reconstruct the root hash, level by level
+    /// from the leaves up.
+    pub fn hashes(&self) -> &[Digest] {
+        &self.hashes
+    }
+
+    /// Returns `true` if the root hash reconstructed from all values and hashes
+    /// of the proof equals `root_hash`.
+    pub fn validate(&self, root_hash: &Digest) -> bool {
+        let mut leaves = Vec::with_capacity(self.values.len());
+        for &(index, ref value) in &self.values {
+            if leaves.last().is_some_and(|&(last, _)| last >= index) {
+                return false;
+            }
+            leaves.push((index, self.scheme.leaf(value)));
+        }
+        reconstruct_root(&self.scheme, leaves, self.tree_size, &self.hashes) == Some(*root_hash)
+    }
+}
+
+/// Calculates the root hash of the tree of `tree_size` elements from the hashes of
+/// `leaves` with ascending indices and sibling `hashes` ordered as `MultiProof` does.
+/// Returns `None` if the hashes don't match the positions of the leaves.
+pub fn reconstruct_root<H>(scheme: &HashScheme<H>,
+                           leaves: Vec<(usize, Digest)>,
+                           tree_size: usize,
+                           hashes: &[Digest])
+                           -> Option<Digest>
+    where H: MerkleHasher
+{
+    match leaves.last() {
+        Some(&(index, _)) if index < tree_size => {}
+        _ => return None,
+    }
+
+    let mut nodes = leaves;
+    let mut hashes = hashes.iter();
+    let mut size = tree_size;
+    while size > 1 {
+        let mut parents = Vec::with_capacity(nodes.len());
+        let mut i = 0;
+        while i < nodes.len() {
+            let (index, ref hash) = nodes[i];
+            let parent = if index % 2 == 1 {
+                scheme.node(hashes.next()?, hash)
+            } else if i + 1 < nodes.len() && nodes[i + 1].0 == index + 1 {
+                i += 1;
+                scheme.node(hash, &nodes[i].1)
+            } else if index + 1 < size {
+                scheme.node(hash, hashes.next()?)
+            } else {
+                scheme.lone_parent(hash)
+            };
+            parents.push((index / 2, parent));
+            i += 1;
+        }
+        nodes = parents;
+        size = size / 2 + size % 2;
+    }
+
+    if hashes.next().is_none() {
+        Some(nodes[0].1)
+    } else {
+        None
+    }
+}
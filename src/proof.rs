@@ -1,36 +1,230 @@
-use std::fmt::Display;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
 
+use digest::Digest;
 use merkle_tree::ProofNode;
-use hash_utils::{create_leaf_hash, create_node_hash};
+use hash_utils::{HashMode, HashScheme};
+use hasher::{MerkleHasher, Sha256};
+use leaf::AsLeafBytes;
 
+/// Proof that a value is a leaf of the `MerkleTree` with a particular root hash.
+/// Hashes are checked with the hash function `H`, which is SHA-256 by default.
 #[derive(Debug)]
-pub struct Proof<T: Display> {
-    root_hash: String,
+pub struct Proof<T: AsLeafBytes, H: MerkleHasher = Sha256> {
+    root_hash: Digest,
     value: T,
+    leaf_index: usize,
+    tree_size: usize,
     path: Vec<ProofNode>,
+    scheme: HashScheme<H>,
 }
 
 impl<T> Proof<T>
-    where T: Display
+    where T: AsLeafBytes
 {
-    pub fn new(root_hash: String, value: T, path: Vec<ProofNode>) -> Self {
+    /// Creates new proof of `value` placed at `leaf_index` of the tree with `tree_size`
+    /// elements from the hashes on its path to `root_hash`. Hashes are checked with the
+    /// default hashing rules, use `MerkleTreeBuilder::proof` to change them.
+    pub fn new(root_hash: Digest,
+               value: T,
+               leaf_index: usize,
+               tree_size: usize,
+               path: Vec<ProofNode>)
+               -> Self {
+        Self::with_hasher(root_hash, value, leaf_index, tree_size, path, Sha256)
+    }
+}
+
+impl<T, H> Proof<T, H>
+    where T: AsLeafBytes,
+          H: MerkleHasher
+{
+    /// Creates new proof like `new` does, which uses `hasher` to calculate hashes.
+    pub fn with_hasher(root_hash: Digest,
+                       value: T,
+                       leaf_index: usize,
+                       tree_size: usize,
+                       path: Vec<ProofNode>,
+                       hasher: H)
+                       -> Self {
+        let scheme = HashScheme::with_hasher(hasher);
+        Self::with_scheme(root_hash, value, leaf_index, tree_size, path, scheme)
+    }
+
+    pub(crate) fn with_scheme(root_hash: Digest,
+                              value: T,
+                              leaf_index: usize,
+                              tree_size: usize,
+                              path: Vec<ProofNode>,
+                              scheme: HashScheme<H>)
+                              -> Self {
         Proof {
             root_hash: root_hash,
             value: value,
+            leaf_index: leaf_index,
+            tree_size: tree_size,
             path: path,
+            scheme: scheme,
         }
     }
 
-    pub fn validate(&self, root_hash: &str) -> bool {
-        let mut hash = create_leaf_hash(&self.value);
+    /// Returns the root hash the proof claims to lead to.
+    pub fn root_hash(&self) -> &Digest {
+        &self.root_hash
+    }
+
+    /// Returns the value the proof was built for.
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Returns hashes on the path from the leaf to the root.
+    pub fn path(&self) -> &[ProofNode] {
+        &self.path
+    }
+
+    /// Returns the index of the leaf the proof was built for.
+    pub fn leaf_index(&self) -> usize {
+        self.leaf_index
+    }
+
+    /// Returns the number of elements in the tree the proof was built for.
+    pub fn tree_size(&self) -> usize {
+        self.tree_size
+    }
+
+    pub(crate) fn hash_mode(&self) -> HashMode {
+        self.scheme.hash_mode()
+    }
+
+    /// Returns `true` if the path of the proof leads from `value` to `root_hash`.
+    pub fn validate(&self, root_hash: &Digest) -> bool {
+        let mut hash = self.scheme.leaf(&self.value);
 
         for node in &self.path {
             hash = match node {
-                &ProofNode::Left(ref proof_hash) => create_node_hash(proof_hash, &hash),
-                &ProofNode::Right(ref proof_hash) => create_node_hash(&hash, proof_hash),
+                &ProofNode::Left(ref proof_hash) => self.scheme.node(proof_hash, &hash),
+                &ProofNode::Right(ref proof_hash) => self.scheme.node(&hash, proof_hash),
             };
         }
 
-        hash == root_hash
+        hash == *root_hash
+    }
+
+    /// Returns `true` if the path of the proof leads from `value` to `root_hash` and
+    /// matches the position of `value` in the tree. Directions and length of the path
+    /// are recalculated from `leaf_index`, `tree_size` and the `OddNodePolicy` of the
+    /// tree, so the proof is rejected if it was built for another leaf or another size
+    /// of the tree.
+    pub fn validate_strict(&self, root_hash: &Digest) -> bool {
+        if self.leaf_index >= self.tree_size {
+            return false;
+        }
+
+        let mut index = self.leaf_index;
+        let mut size = self.tree_size;
+        let mut hash = self.scheme.leaf(&self.value);
+        let mut path = self.path.iter();
+
+        while size > 1 {
+            if index % 2 == 1 {
+                match path.next() {
+                    Some(ProofNode::Left(proof_hash)) => {
+                        hash = self.scheme.node(proof_hash, &hash)
+                    }
+                    _ => return false,
+                }
+            } else if index + 1 < size {
+                match path.next() {
+                    Some(ProofNode::Right(proof_hash)) => {
+                        hash = self.scheme.node(&hash, proof_hash)
+                    }
+                    _ => return false,
+                }
+            } else if let Some(sibling) = self.scheme.lone_sibling(&hash) {
+                // The last node of the level without sibling is paired with a hash
+                // defined by the policy, promoted nodes have no entry in the path.
+                match path.next() {
+                    Some(ProofNode::Right(proof_hash)) if *proof_hash == sibling => {
+                        hash = self.scheme.node(&hash, proof_hash)
+                    }
+                    _ => return false,
+                }
+            }
+            index /= 2;
+            size = size / 2 + size % 2;
+        }
+
+        path.next().is_none() && hash == *root_hash
+    }
+
+    /// Returns `true` if the proof is valid against its own root hash.
+    /// The path is checked the same way as by `validate_strict`.
+    pub fn verify(&self) -> bool {
+        self.validate_strict(&self.root_hash)
     }
+
+    /// Checks the proof against `trusted_root`, e.g. a root hash pinned by the client.
+    /// Returns an error if the path doesn't lead to the root hash of the proof or if that
+    /// root hash differs from `trusted_root`.
+    pub fn verify_against(&self, trusted_root: &Digest) -> Result<(), VerifyError> {
+        if !self.verify() {
+            Err(VerifyError::InvalidPath)
+        } else if self.root_hash != *trusted_root {
+            Err(VerifyError::RootMismatch)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Errors which can occur while building a proof.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProofError {
+    /// The requested value is not present in the tree.
+    ValueNotPresent,
+    /// The tree has no elements to prove.
+    EmptyTree,
+    /// The requested index is out of the range of the tree.
+    IndexOutOfRange,
+    /// The proof is defined only for trees with `OddNodePolicy::Promote`.
+    UnsupportedPolicy,
+    /// The value which absence should be proven is present in the tree.
+    ValuePresent,
 }
+
+impl Display for ProofError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let message = match *self {
+            ProofError::ValueNotPresent => "value is not present in the tree",
+            ProofError::EmptyTree => "tree is empty",
+            ProofError::IndexOutOfRange => "index is out of range",
+            ProofError::UnsupportedPolicy => "proof requires OddNodePolicy::Promote",
+            ProofError::ValuePresent => "value is present in the tree",
+        };
+        write!(f, "{}", message)
+    }
+}
+
+impl Error for ProofError {}
+
+/// Errors which can occur while verifying a proof.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerifyError {
+    /// The path of the proof doesn't lead from the value to the root hash of the proof.
+    InvalidPath,
+    /// The root hash of the proof differs from the trusted one.
+    RootMismatch,
+}
+
+impl Display for VerifyError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let message = match *self {
+            VerifyError::InvalidPath => "path doesn't lead to the root hash of the proof",
+            VerifyError::RootMismatch => "root hash of the proof differs from the trusted one",
+        };
+        write!(f, "{}", message)
+    }
+}
+
+impl Error for VerifyError {}
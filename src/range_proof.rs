@@ -0,0 +1,76 @@
+use digest::Digest;
+use hash_utils::HashScheme;
+use hasher::{MerkleHasher, Sha256};
+use leaf::AsLeafBytes;
+use multiproof::reconstruct_root;
+
+/// Proof that a sequence of values is exactly the range of elements from `start` to `end`
+/// (exclusive) of the `MerkleTree` with a particular root hash. Contains only the hashes
+/// of the subtrees bordering the range. Built by `MerkleTree::get_range_proof`.
+#[derive(Debug)]
+pub struct RangeProof<H: MerkleHasher = Sha256> {
+    start: usize,
+    end: usize,
+    tree_size: usize,
+    hashes: Vec<Digest>,
+    scheme: HashScheme<H>,
+}
+
+impl<H: MerkleHasher> RangeProof<H> {
+    pub(crate) fn with_scheme(start: usize,
+                              end: usize,
+                              tree_size: usize,
+                              hashes: Vec<Digest>,
+                              scheme: HashScheme<H>)
+                              -> Self {
+        RangeProof {
+            start: start,
+            end: end,
+            tree_size: tree_size,
+            hashes: hashes,
+            scheme: scheme,
+        }
+    }
+
+    /// Returns the index of the first element of the range.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Returns the index after the last element of the range.
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    /// Returns the number of elements in the tree the proof was built for.
+    pub fn tree_size(&self) -> usize {
+        self.tree_size
+    }
+
+    /// Returns hashes of the subtrees bordering the range, level by level from the leaves up.
+    pub fn hashes(&self) -> &[Digest] {
+        &self.hashes
+    }
+
+    /// Returns `true` if `values` in the given order are exactly the elements starting at
+    /// `start` of the tree of `tree_size` elements with `root_hash`. Missing, extra or
+    /// reordered values are rejected. Both `tree_size` and `start` must be trusted, since
+    /// the proof is rejected only if it was built for another size or position.
+    pub fn validate<T: AsLeafBytes>(&self,
+                                    root_hash: &Digest,
+                                    tree_size: usize,
+                                    start: usize,
+                                    values: &[T])
+                                    -> bool {
+        if values.is_empty() || self.tree_size != tree_size || self.start != start ||
+           self.end.checked_sub(start) != Some(values.len()) {
+            return false;
+        }
+
+        let leaves = values.iter()
+            .enumerate()
+            .map(|(i, value)| (self.start + i, self.scheme.leaf(value)))
+            .collect();
+        reconstruct_root(&self.scheme, leaves, self.tree_size, &self.hashes) == Some(*root_hash)
+    }
+}
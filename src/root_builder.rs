@@ -0,0 +1,106 @@
+use digest::Digest;
+use hash_utils::HashScheme;
+use hasher::{MerkleHasher, Sha256};
+use leaf::AsLeafBytes;
+
+/// Calculates the root hash of a sequence of values without storing them. Only the
+/// hashes of complete subtrees are kept, so it takes `O(log n)` memory. The root hash
+/// is the same as the one of `MerkleTree` built from the same values with the same
+/// hashing rules.
+/// # Examples
+///
+/// ```
+/// extern crate merkle_tree;
+/// use merkle_tree::{MerkleRootBuilder, MerkleTree};
+///
+/// let mut builder = MerkleRootBuilder::new();
+/// builder.push(1);
+/// builder.extend(vec![2, 3]);
+/// let tree = MerkleTree::from_vec(vec![1, 2, 3]);
+/// assert_eq!(tree.root_hash(), Some(&builder.root()));
+/// ```
+#[derive(Clone, Debug)]
+pub struct MerkleRootBuilder<H: MerkleHasher = Sha256> {
+    count: usize,
+    stack: Vec<(usize, Digest)>,
+    scheme: HashScheme<H>,
+}
+
+impl MerkleRootBuilder {
+    /// Creates new root builder with SHA-256 hash function and default hashing rules.
+    /// Use `MerkleTreeBuilder::root_builder` to change them.
+    pub fn new() -> Self {
+        Self::with_scheme(HashScheme::with_hasher(Sha256))
+    }
+}
+
+impl Default for MerkleRootBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<H: MerkleHasher> MerkleRootBuilder<H> {
+    pub(crate) fn with_scheme(scheme: HashScheme<H>) -> Self {
+        MerkleRootBuilder {
+            count: 0,
+            stack: Vec::new(),
+            scheme: scheme,
+        }
+    }
+
+    /// Adds `value` as the next leaf.
+    pub fn push<T: AsLeafBytes>(&mut self, value: T) {
+        let mut level = 0;
+        let mut hash = self.scheme.leaf(&value);
+        // Merge complete subtrees of the same height, like carries of a binary counter.
+        while let Some(&(last_level, last_hash)) = self.stack.last() {
+            if last_level != level {
+                break;
+            }
+            self.stack.pop();
+            hash = self.scheme.node(&last_hash, &hash);
+            level += 1;
+        }
+        self.stack.push((level, hash));
+        self.count += 1;
+    }
+
+    /// Returns the number of leaves added so far.
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    /// Returns `true` if no leaves were added.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Returns the root hash of the tree with leaves added so far.
+    pub fn root(&self) -> Digest {
+        let mut subtrees = self.stack.iter().rev();
+        let (mut level, mut hash) = match subtrees.next() {
+            Some(&subtree) => subtree,
+            None => return self.scheme.empty_root(),
+        };
+
+        for &(left_level, ref left_hash) in subtrees {
+            // The right-most node has no sibling on the levels below the next subtree.
+            while level < left_level {
+                hash = self.scheme.lone_parent(&hash);
+                level += 1;
+            }
+            hash = self.scheme.node(left_hash, &hash);
+            level += 1;
+        }
+        hash
+    }
+}
+
+impl<T: AsLeafBytes, H: MerkleHasher> Extend<T> for MerkleRootBuilder<H> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use std::cmp::Ordering;
+use std::collections::vec_deque::Iter;
+
+use digest::Digest;
+use hash_utils::{HashMode, HashScheme, OddNodePolicy};
+use hasher::{MerkleHasher, Sha256};
+use leaf::AsLeafBytes;
+use merkle_tree::MerkleTree;
+use proof::{Proof, ProofError};
+
+/// `MerkleTree` which keeps its values sorted and unique, so it can prove that a value
+/// is not present by proving its two adjacent neighbours. Values are always hashed in
+/// `HashMode::DomainSeparated`.
+/// # Examples
+///
+/// ```
+/// extern crate merkle_tree;
+/// use merkle_tree::SortedMerkleTree;
+///
+/// let revoked = SortedMerkleTree::from_vec(vec![40, 10, 30]);
+/// let proof = revoked.get_absence_proof(20).unwrap();
+/// assert_eq!(Some(&10), proof.left().map(|p| p.value()));
+/// assert_eq!(Some(&30), proof.right().map(|p| p.value()));
+/// assert!(proof.validate(revoked.root_hash().unwrap(), revoked.len()));
+/// ```
+#[derive(Debug)]
+pub struct SortedMerkleTree<T: AsLeafBytes + Clone + Ord, H: MerkleHasher = Sha256> {
+    tree: MerkleTree<T, H>,
+}
+
+impl<T: AsLeafBytes + Clone + Ord> SortedMerkleTree<T> {
+    /// Creates new, empty `SortedMerkleTree`.
+    pub fn new() -> Self {
+        Self::with_scheme(Self::default_scheme())
+    }
+
+    /// Creates `SortedMerkleTree` from `Vec` of elements. Elements are sorted and
+    /// duplicates are removed.
+    pub fn from_vec(data: Vec<T>) -> Self {
+        Self::from_vec_with_scheme(data, Self::default_scheme())
+    }
+
+    fn default_scheme() -> HashScheme<Sha256> {
+        HashScheme::new(Sha256, HashMode::DomainSeparated, OddNodePolicy::default())
+    }
+}
+
+impl<T: AsLeafBytes + Clone + Ord> Default for SortedMerkleTree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, H> SortedMerkleTree<T, H>
+    where T: AsLeafBytes + Clone + Ord,
+          H: MerkleHasher
+{
+    pub(crate) fn with_scheme(scheme: HashScheme<H>) -> Self {
+        SortedMerkleTree { tree: MerkleTree::with_scheme(scheme) }
+    }
+
+    pub(crate) fn from_vec_with_scheme(data: Vec<T>, scheme: HashScheme<H>) -> Self {
+        let mut data = data;
+        data.sort();
+        data.dedup();
+        SortedMerkleTree { tree: MerkleTree::from_vec_with_scheme(data, scheme) }
+    }
+
+    /// Inserts `value` at its position in order. Returns `false` if the tree already
+    /// contains it.
+    pub fn insert(&mut self, value: T) -> bool {
+        match self.search(&value) {
+            Ok(_) => false,
+            Err(index) => {
+                self.tree.insert(index, value);
+                true
+            }
+        }
+    }
+
+    /// Removes `value` from the tree. Returns `false` if the tree doesn't contain it.
+    pub fn remove(&mut self, value: &T) -> bool {
+        match self.search(value) {
+            Ok(index) => self.tree.remove(index),
+            Err(_) => false,
+        }
+    }
+
+    /// Returns `true` if the tree contains `value`.
+    pub fn contains(&self, value: &T) -> bool {
+        self.search(value).is_ok()
+    }
+
+    /// Returns the number of elements in the tree.
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Returns `true` if the tree is empty.
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Returns root hash of the tree.
+    pub fn root_hash(&self) -> Option<&Digest> {
+        self.tree.root_hash()
+    }
+
+    /// Returns an iterator over the elements in ascending order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.tree.iter()
+    }
+
+    /// Returns the proof for checking if `value` really in tree, or an error if the
+    /// tree is empty or doesn't contain `value`.
+    pub fn get_proof(&self, value: T) -> Result<Proof<T, H>, ProofError> {
+        match self.search(&value) {
+            Ok(index) => self.tree.get_proof_at(index),
+            Err(_) if self.is_empty() => Err(ProofError::EmptyTree),
+            Err(_) => Err(ProofError::ValueNotPresent),
+        }
+    }
+
+    /// Returns the proof that `value` is not in the tree, which consists of the proofs
+    /// of its neighbours. Returns an error if the tree is empty or contains `value`.
+    pub fn get_absence_proof(&self, value: T) -> Result<AbsenceProof<T, H>, ProofError> {
+        if self.is_empty() {
+            return Err(ProofError::EmptyTree);
+        }
+        let index = match self.search(&value) {
+            Ok(_) => return Err(ProofError::ValuePresent),
+            Err(index) => index,
+        };
+
+        let left = if index > 0 {
+            Some(self.tree.get_proof_at(index - 1)?)
+        } else {
+            None
+        };
+        let right = if index < self.len() {
+            Some(self.tree.get_proof_at(index)?)
+        } else {
+            None
+        };
+        Ok(AbsenceProof::new(value, left, right))
+    }
+
+    /// Returns the index of `value` or the index it should be inserted at.
+    fn search(&self, value: &T) -> Result<usize, usize> {
+        let (mut low, mut high) = (0, self.len());
+        while low < high {
+            let middle = (low + high) / 2;
+            match self.tree.get(middle).unwrap().cmp(value) {
+                Ordering::Less => low = middle + 1,
+                Ordering::Equal => return Ok(middle),
+                Ordering::Greater => high = middle,
+            }
+        }
+        Err(low)
+    }
+}
+
+/// Proof that a value is not a leaf of the `SortedMerkleTree` with a particular root hash.
+/// Consists of the proofs of the closest smaller and greater values, one of them is
+/// absent if the value is out of the range of the tree.
+#[derive(Debug)]
+pub struct AbsenceProof<T: AsLeafBytes + Ord, H: MerkleHasher = Sha256> {
+    value: T,
+    left: Option<Proof<T, H>>,
+    right: Option<Proof<T, H>>,
+}
+
+impl<T, H> AbsenceProof<T, H>
+    where T: AsLeafBytes + Ord,
+          H: MerkleHasher
+{
+    /// Creates new proof that `value` is absent from the proofs of its neighbours.
+    pub fn new(value: T, left: Option<Proof<T, H>>, right: Option<Proof<T, H>>) -> Self {
+        AbsenceProof {
+            value: value,
+            left: left,
+            right: right,
+        }
+    }
+
+    /// Returns the value the proof was built for.
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Returns the proof of the closest value smaller than the absent one.
+    pub fn left(&self) -> Option<&Proof<T, H>> {
+        self.left.as_ref()
+    }
+
+    /// Returns the proof of the closest value greater than the absent one.
+    pub fn right(&self) -> Option<&Proof<T, H>> {
+        self.right.as_ref()
+    }
+
+    /// Returns `true` if both neighbours are strictly valid leaves of the tree of
+    /// `tree_size` elements with `root_hash`, they are adjacent and the value lies
+    /// strictly between them. Both `root_hash` and `tree_size` must be trusted, e.g.
+    /// published together, since the proofs are hashed in `HashMode::DomainSeparated`
+    /// and checked only against them. Relies on the tree with `root_hash` being sorted,
+    /// as `SortedMerkleTree` guarantees.
+    pub fn validate(&self, root_hash: &Digest, tree_size: usize) -> bool {
+        let neighbours = [&self.left, &self.right];
+        for proof in neighbours.iter().filter_map(|proof| proof.as_ref()) {
+            if proof.hash_mode() != HashMode::DomainSeparated || proof.tree_size() != tree_size ||
+               !proof.validate_strict(root_hash) {
+                return false;
+            }
+        }
+
+        match (&self.left, &self.right) {
+            (Some(left), Some(right)) => {
+                left.leaf_index() + 1 == right.leaf_index() &&
+                *left.value() < self.value && self.value < *right.value()
+            }
+            (Some(left), None) => {
+                left.leaf_index() + 1 == tree_size && *left.value() < self.value
+            }
+            (None, Some(right)) => right.leaf_index() == 0 && self.value < *right.value(),
+            (None, None) => false,
+        }
+    }
+}
@@ -2,13 +2,15 @@
 
 use test::Bencher;
 use merkle_tree::MerkleTree;
+use hash_utils::{HashMode, create_leaf_hash};
+use hasher::Sha256;
 
 #[bench]
 fn benchmark_good_validation(b: &mut Bencher) {
     let data = (0..10000).collect::<Vec<_>>();
     let db = MerkleTree::from_vec(data);
     let root_hash = db.root_hash();
-    let proof = db.get_proof(557);
+    let proof = db.get_proof(557).unwrap();
 
     b.iter(|| { proof.validate(root_hash.unwrap()); })
 }
@@ -17,10 +19,10 @@ fn benchmark_good_validation(b: &mut Bencher) {
 fn benchmark_bad_validation(b: &mut Bencher) {
     let data = (0..10000).collect::<Vec<_>>();
     let db = MerkleTree::from_vec(data);
-    let root_hash = db.root_hash();
-    let proof = db.get_proof(242342342);
+    let root_hash = create_leaf_hash(&Sha256, HashMode::Binary, &242342342);
+    let proof = db.get_proof(557).unwrap();
 
-    b.iter(|| { proof.validate(root_hash.unwrap()); })
+    b.iter(|| { proof.validate(&root_hash); })
 }
 
 #[bench]
@@ -38,3 +40,19 @@ fn benchmark_creation_from_vec_with_1000_elements(b: &mut Bencher) {
                let _ = MerkleTree::from_vec(v);
            });
 }
+
+#[bench]
+fn benchmark_creation_from_vec_with_10000_elements(b: &mut Bencher) {
+    b.iter(|| {
+               let v = (0..10000).collect::<Vec<_>>();
+               let _ = MerkleTree::from_vec(v);
+           });
+}
+
+#[bench]
+fn benchmark_creation_from_vec_with_100000_elements(b: &mut Bencher) {
+    b.iter(|| {
+               let v = (0..100000).collect::<Vec<_>>();
+               let _ = MerkleTree::from_vec(v);
+           });
+}
@@ -2,20 +2,63 @@
 
 mod benchmarks;
 
-use merkle_tree::{MerkleTree, calculate_height};
+use std::sync::{Arc, RwLock};
+use std::thread;
+
+use builder::MerkleTreeBuilder;
+use consistency::ConsistencyProof;
+use digest::Digest;
+use merkle_tree::{MerkleTree, ProofNode, calculate_height};
+use multiproof::MultiProof;
 use hash_utils::*;
+use hasher::*;
+use leaf::AsLeafBytes;
+use proof::{Proof, ProofError, VerifyError};
+use root_builder::MerkleRootBuilder;
+use sorted_tree::{AbsenceProof, SortedMerkleTree};
 
 #[test]
 fn test_empty_tree_hash() {
     let db: MerkleTree<u32> = MerkleTree::new();
-    assert_eq!(&"5feceb66ffc86f38d952786c6d696c79c2dbc239dd4e91b46729d73a27fb57e9".to_string(),
-               db.root_hash().unwrap_or(&"None".to_string()));
+    assert_eq!(Some(&Digest::new([0; 32])), db.root_hash());
+
+    let db: MerkleTree<u32> = MerkleTreeBuilder::new().hash_mode(HashMode::DomainSeparated).build();
+    assert_eq!("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+               db.root_hash().unwrap().to_string());
+
+    let db: MerkleTree<&str> = MerkleTreeBuilder::new().hash_mode(HashMode::Compat).build();
+    assert_eq!("5feceb66ffc86f38d952786c6d696c79c2dbc239dd4e91b46729d73a27fb57e9",
+               db.root_hash().unwrap().to_string());
+}
+
+#[test]
+fn test_empty_root_is_distinct() {
+    for &mode in &[HashMode::Binary, HashMode::DomainSeparated] {
+        let mut db = MerkleTreeBuilder::new().hash_mode(mode).build();
+        let empty_root = *db.root_hash().unwrap();
+        for value in [vec![], vec![0u8], b"0".to_vec()] {
+            db.push(value);
+            assert!(empty_root != *db.root_hash().unwrap());
+            db.pop();
+            assert_eq!(empty_root, *db.root_hash().unwrap());
+        }
+    }
+
+    let custom = Digest::new([0xff; 32]);
+    let mut db = MerkleTreeBuilder::new().empty_root(custom).build();
+    assert_eq!(Some(&custom), db.root_hash());
+    db.push(1);
+    assert!(Some(&custom) != db.root_hash());
+    db.pop();
+    assert_eq!(Some(&custom), db.root_hash());
 }
 
 #[test]
 fn test_height_and_len() {
-    let root_hash = "8fed6b1d66ea88efd0c1b7e752334a08128791e974dce6f4c14902fa0e33d5e1".to_string();
-    let mut db = MerkleTree::new();
+    let root_hash = "8fed6b1d66ea88efd0c1b7e752334a08128791e974dce6f4c14902fa0e33d5e1"
+        .parse::<Digest>()
+        .unwrap();
+    let mut db = MerkleTreeBuilder::new().hash_mode(HashMode::Compat).build();
 
     db.push("1");
     db.push("2");
@@ -32,13 +75,13 @@ fn test_height_and_len() {
 
     assert_eq!(12, db.len());
     assert_eq!(4, db.height());
-    assert_eq!(&root_hash, db.root_hash().unwrap_or(&"None".to_string()));
+    assert_eq!(Some(&root_hash), db.root_hash());
 
-    let good_proof = db.get_proof("6");
-    assert_eq!(true, good_proof.validate(root_hash.as_ref()));
+    let good_proof = db.get_proof("6").unwrap();
+    assert_eq!(true, good_proof.validate(&root_hash));
 
     let bad_proof = db.get_proof("1231231231");
-    assert_eq!(false, bad_proof.validate(root_hash.as_ref()));
+    assert_eq!(Some(ProofError::ValueNotPresent), bad_proof.err());
 }
 
 #[test]
@@ -68,26 +111,30 @@ fn test_height_calc() {
 
 #[test]
 fn test_size() {
-    let data = empty_hash();
-    assert_eq!(64, data.len());
+    let data = empty_hash(&Sha256, HashMode::Binary);
+    assert_eq!(32, data.as_bytes().len());
+    assert_eq!(64, data.to_string().len());
 }
 
 #[test]
 fn test_hash() {
     assert_eq!("2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824",
-               create_leaf_hash(&"hello".to_string()));
+               create_leaf_hash(&Sha256, HashMode::Binary, &"hello".to_string()).to_string());
 }
 
 #[test]
 fn test_combined_hash() {
-    let hello_hash = create_leaf_hash(&"hello".to_string());
-    let world_hash = create_leaf_hash(&"world".to_string());
-    let node_hash = create_node_hash(&hello_hash, &world_hash);
+    let hello_hash = create_leaf_hash(&Sha256, HashMode::Binary, &"hello".to_string());
+    let world_hash = create_leaf_hash(&Sha256, HashMode::Binary, &"world".to_string());
+    let node_hash = create_node_hash(&Sha256, HashMode::Compat, &hello_hash, &world_hash);
     assert_eq!("15e178b71fae8849ee562c9cc0d7ea322fba6cd495411329d47234479167cc8b",
-               node_hash);
+               node_hash.to_string());
+    let node_hash = create_node_hash(&Sha256, HashMode::Binary, &hello_hash, &world_hash);
+    assert_eq!("7305db9b2abccd706c256db3d97e5ff48d677cfe4d3a5904afb7da0e3950e1e2",
+               node_hash.to_string());
 }
 
-use std::fmt::{Display, Formatter, Result};
+use std::borrow::Cow;
 
 #[derive(Clone)]
 struct Person {
@@ -95,9 +142,9 @@ struct Person {
     name: String,
 }
 
-impl Display for Person {
-    fn fmt(&self, f: &mut Formatter) -> Result {
-        write!(f, "age: {}, name: {}", self.age, self.name)
+impl AsLeafBytes for Person {
+    fn leaf_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned((self.age, self.name.as_str()).leaf_bytes().into_owned())
     }
 }
 
@@ -123,16 +170,17 @@ fn test_with_structs() {
     assert_eq!(4, db.len());
 
     let good_proof = db.get_proof(Person {
-                                      age: 3,
-                                      name: "Bob".to_string(),
-                                  });
+                                          age: 3,
+                                          name: "Bob".to_string(),
+                                      })
+        .unwrap();
     assert!(good_proof.validate(db.root_hash().unwrap()));
 
     let bad_proof = db.get_proof(Person {
                                      age: 3,
                                      name: "Bobx".to_string(),
                                  });
-    assert!(!bad_proof.validate(db.root_hash().unwrap()));
+    assert!(bad_proof.is_err());
 
 }
 
@@ -161,18 +209,837 @@ fn test_get_values() {
 #[test]
 fn test_root_calculation() {
     let db = MerkleTree::from_vec(vec![1, 2, 3, 4]);
-    let h1 = create_leaf_hash(&1);
-    let h2 = create_leaf_hash(&2);
-    let h3 = create_leaf_hash(&3);
-    let h4 = create_leaf_hash(&4);
-    let h12 = create_node_hash(&h1, &h2);
-    let h34 = create_node_hash(&h3, &h4);
-    let root = create_node_hash(&h12, &h34);
+    let h1 = create_leaf_hash(&Sha256, HashMode::Binary, &1);
+    let h2 = create_leaf_hash(&Sha256, HashMode::Binary, &2);
+    let h3 = create_leaf_hash(&Sha256, HashMode::Binary, &3);
+    let h4 = create_leaf_hash(&Sha256, HashMode::Binary, &4);
+    let h12 = create_node_hash(&Sha256, HashMode::Binary, &h1, &h2);
+    let h34 = create_node_hash(&Sha256, HashMode::Binary, &h3, &h4);
+    let root = create_node_hash(&Sha256, HashMode::Binary, &h12, &h34);
     assert_eq!(&root, db.root_hash().unwrap());
 
-    let good_proof = db.get_proof(2);
+    let good_proof = db.get_proof(2).unwrap();
     assert!(good_proof.validate(db.root_hash().unwrap()));
     let bad_proof = db.get_proof(663);
-    assert!(!bad_proof.validate(db.root_hash().unwrap()));
+    assert_eq!(Some(ProofError::ValueNotPresent), bad_proof.err());
+
+}
+
+#[test]
+fn test_push_matches_full_calculation() {
+    let mut db = MerkleTree::new();
+    for i in 0..70 {
+        db.push(i);
+        let expected = MerkleTree::from_vec((0..i + 1).collect::<Vec<_>>());
+        assert_eq!(expected.height(), db.height());
+        assert_eq!(expected.root_hash(), db.root_hash());
+    }
+}
+
+#[test]
+fn test_remove_matches_full_calculation() {
+    for index in 0..17 {
+        let mut db = MerkleTree::from_vec((0..17).collect::<Vec<_>>());
+        assert!(db.remove(index));
+        let mut values = (0..17).collect::<Vec<_>>();
+        values.remove(index);
+        let expected = MerkleTree::from_vec(values);
+        assert_eq!(expected.height(), db.height());
+        assert_eq!(expected.root_hash(), db.root_hash());
+    }
+}
+
+#[test]
+fn test_pop_element() {
+    let mut db = MerkleTree::from_vec((0..33).collect::<Vec<_>>());
+    for i in (0..33).rev() {
+        assert_eq!(Some(i), db.pop());
+        let expected = MerkleTree::from_vec((0..i).collect::<Vec<_>>());
+        assert_eq!(expected.height(), db.height());
+        assert_eq!(expected.root_hash(), db.root_hash());
+    }
+    assert_eq!(None, db.pop());
+    assert!(db.is_empty());
+}
+
+#[test]
+fn test_set_element() {
+    let mut db = MerkleTree::from_vec((0..13).collect::<Vec<_>>());
+    let mut values = (0..13).collect::<Vec<_>>();
+    for index in 0..13 {
+        assert_eq!(Some(index), db.set(index, index * 100));
+        values[index] = index * 100;
+        let expected = MerkleTree::from_vec(values.clone());
+        assert_eq!(expected.root_hash(), db.root_hash());
+    }
+    assert_eq!(None, db.set(13, 0));
+    assert_eq!(13, db.len());
+}
+
+#[test]
+fn test_insert_element() {
+    for index in 0..18 {
+        let mut db = MerkleTree::from_vec((0..17).collect::<Vec<_>>());
+        db.insert(index, 100);
+        let mut values = (0..17).collect::<Vec<_>>();
+        values.insert(index, 100);
+        let expected = MerkleTree::from_vec(values.clone());
+        assert_eq!(expected.height(), db.height());
+        assert_eq!(expected.root_hash(), db.root_hash());
+        assert_eq!(Some(values), db.get_values());
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_insert_out_of_bounds() {
+    let mut db = MerkleTree::from_vec(vec![1, 2, 3]);
+    db.insert(4, 5);
+}
+
+#[test]
+fn test_batch_append() {
+    let mut db = MerkleTree::from_vec((0..5).collect::<Vec<_>>());
+    let mut sequential = MerkleTree::from_vec((0..5).collect::<Vec<_>>());
+    for i in 5..40 {
+        sequential.push(i);
+    }
+
+    db.append((5..20).collect::<Vec<_>>());
+    db.extend_from_slice(&(20..30).collect::<Vec<_>>());
+    db.extend(30..40);
+    db.extend(Vec::new());
+
+    assert_eq!(40, db.len());
+    assert_eq!(sequential.height(), db.height());
+    assert_eq!(sequential.root_hash(), db.root_hash());
+}
+
+#[test]
+fn test_proof_by_index() {
+    let db = MerkleTree::from_vec(vec![5, 7, 5, 9, 5, 5, 1]);
+    for index in 0..db.len() {
+        let proof = db.get_proof_at(index).unwrap();
+        assert!(proof.validate(db.root_hash().unwrap()));
+    }
+    assert_eq!(Some(ProofError::IndexOutOfRange), db.get_proof_at(7).err());
+}
+
+#[test]
+fn test_proofs_for_duplicated_values() {
+    let db = MerkleTree::from_vec(vec![1, 2, 1, 2, 5, 7, 5, 9]);
+    let proofs = db.get_proofs_for(&5);
+    assert_eq!(vec![4, 6], proofs.iter().map(|p| p.0).collect::<Vec<_>>());
+    for (_, proof) in &proofs {
+        assert!(proof.validate(db.root_hash().unwrap()));
+    }
+
+    let proofs = db.get_proofs_for(&2);
+    assert_eq!(vec![1, 3], proofs.iter().map(|p| p.0).collect::<Vec<_>>());
+    for (_, proof) in &proofs {
+        assert!(proof.validate(db.root_hash().unwrap()));
+    }
+
+    assert!(db.get_proof(9).unwrap().validate(db.root_hash().unwrap()));
+    assert!(db.get_proofs_for(&3).is_empty());
+}
+
+#[test]
+fn test_proof_errors() {
+    let mut db = MerkleTree::new();
+    assert_eq!(Some(ProofError::EmptyTree), db.get_proof(1).err());
+    assert_eq!(Some(ProofError::EmptyTree), db.get_proof_at(0).err());
+
+    db.push(1);
+    assert!(db.get_proof(1).is_ok());
+    assert_eq!(Some(ProofError::ValueNotPresent), db.get_proof(2).err());
+    assert_eq!(Some(ProofError::IndexOutOfRange), db.get_proof_at(1).err());
+}
+
+#[test]
+fn test_strict_proof_validation() {
+    let db = MerkleTree::from_vec(vec![1, 2, 3, 4, 5]);
+    let root_hash = db.root_hash().unwrap();
+    for index in 0..db.len() {
+        let proof = db.get_proof_at(index).unwrap();
+        assert_eq!(index, proof.leaf_index());
+        assert_eq!(5, proof.tree_size());
+        assert!(proof.validate_strict(root_hash));
+    }
+
+    // Same hashes claimed for another position.
+    let h1 = create_leaf_hash(&Sha256, HashMode::Binary, &1);
+    let h2 = create_leaf_hash(&Sha256, HashMode::Binary, &2);
+    let h3 = create_leaf_hash(&Sha256, HashMode::Binary, &3);
+    let h4 = create_leaf_hash(&Sha256, HashMode::Binary, &4);
+    let h5 = create_leaf_hash(&Sha256, HashMode::Binary, &5);
+    let h55 = create_node_hash(&Sha256, HashMode::Binary, &h5, &h5);
+    let h5555 = create_node_hash(&Sha256, HashMode::Binary, &h55, &h55);
+    let h34 = create_node_hash(&Sha256, HashMode::Binary, &h3, &h4);
+    let h12 = create_node_hash(&Sha256, HashMode::Binary, &h1, &h2);
+    let h1234 = create_node_hash(&Sha256, HashMode::Binary, &h12, &h34);
+    let path = vec![ProofNode::Right(h2),
+                    ProofNode::Right(h34),
+                    ProofNode::Right(h5555)];
+    let proof = Proof::new(*root_hash, 1, 0, 5, path);
+    assert!(proof.validate_strict(root_hash));
+    let path = vec![ProofNode::Right(h2),
+                    ProofNode::Right(h34),
+                    ProofNode::Right(h5555)];
+    let proof = Proof::new(*root_hash, 1, 2, 5, path);
+    assert!(proof.validate(root_hash));
+    assert!(!proof.validate_strict(root_hash));
+
+    // Path is too short for the size of the tree.
+    let path = vec![ProofNode::Right(h5), ProofNode::Right(h55)];
+    let proof = Proof::new(h5555, 5, 4, 5, path);
+    assert!(proof.validate(&h5555));
+    assert!(!proof.validate_strict(&h5555));
+
+    // Last leaf must be paired with itself.
+    let path = vec![ProofNode::Right(h1),
+                    ProofNode::Right(h55),
+                    ProofNode::Left(h1234)];
+    let proof = Proof::new(*root_hash, 5, 4, 5, path);
+    assert!(!proof.validate_strict(root_hash));
+
+    // Untrusted tree size must not overflow.
+    let proof = Proof::new(*root_hash, 1, 0, usize::MAX, vec![ProofNode::Right(h1)]);
+    assert!(!proof.validate_strict(root_hash));
+}
+
+#[test]
+fn test_verify_against_trusted_root() {
+    let db = MerkleTree::from_vec(vec![1, 2, 3, 4]);
+    let root_hash = db.root_hash().unwrap();
+    let proof = db.get_proof(3).unwrap();
+    assert_eq!(root_hash, proof.root_hash());
+    assert_eq!(&3, proof.value());
+    assert_eq!(2, proof.path().len());
+    assert!(proof.verify());
+    assert_eq!(Ok(()), proof.verify_against(root_hash));
+
+    let other = MerkleTree::from_vec(vec![1, 2, 3, 5]);
+    assert_eq!(Err(VerifyError::RootMismatch),
+               proof.verify_against(other.root_hash().unwrap()));
+
+    let proof = other.get_proof(3).unwrap();
+    let forged = Proof::new(*root_hash, 3, 2, 4, proof.path().to_vec());
+    assert!(!forged.verify());
+    assert_eq!(Err(VerifyError::InvalidPath), forged.verify_against(root_hash));
+}
+
+#[test]
+fn test_hashers() {
+    let hello = ["hello".as_bytes()];
+    assert_eq!("2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824",
+               Sha256.hash(&hello).to_string());
+    assert_eq!("e30d87cfa2a75db545eac4d61baf970366a8357c7f72fa95b52d0accb698f13a",
+               Sha512Trunc256.hash(&hello).to_string());
+    assert_eq!("3338be694f50c5f338814986cdf0686453a888b84f424d792af4b9202398f392",
+               Sha3_256.hash(&hello).to_string());
+    assert_eq!("324dcf027dd4a30a932c441f365a25e86b173defa4b8e58948253471b81b72cf",
+               Blake2b.hash(&hello).to_string());
+    assert_eq!(Sha256.hash(&hello), Sha256.hash(&["he".as_bytes(), "llo".as_bytes()]));
+}
+
+#[cfg(feature = "blake3")]
+#[test]
+fn test_blake3_hasher() {
+    assert_eq!("ea8f163db38682925e4491c5e58d4bb3506ef8c14eb78a86e908c5624a67200f",
+               Blake3.hash(&["hello".as_bytes()]).to_string());
+}
+
+#[test]
+fn test_tree_with_hasher() {
+    let values = (0..11).collect::<Vec<_>>();
+    let default = MerkleTree::from_vec(values.clone());
+    let sha256 = MerkleTree::from_vec_with_hasher(values.clone(), Sha256);
+    assert_eq!(default.root_hash(), sha256.root_hash());
+
+    let mut db = MerkleTree::with_hasher(Sha3_256);
+    db.extend(values.clone());
+    let expected = MerkleTree::from_vec_with_hasher(values, Sha3_256);
+    assert_eq!(expected.root_hash(), db.root_hash());
+    assert!(default.root_hash() != db.root_hash());
+
+    let proof = db.get_proof(7).unwrap();
+    assert!(proof.verify());
+    assert!(proof.validate(db.root_hash().unwrap()));
+    assert!(!proof.validate(default.root_hash().unwrap()));
+}
+
+#[test]
+fn test_digest_from_str() {
+    let hex = "85df8945419d2b5038f7ac83ec1ec6b8267c40fdb3b1e56ff62f6676eb855e70";
+    let digest = hex.parse::<Digest>().unwrap();
+    assert_eq!(hex, digest.to_string());
+    assert_eq!(0x85, digest.as_bytes()[0]);
+    assert_eq!(Some(digest), Digest::from_slice(digest.as_ref()));
+    assert_eq!(digest, hex.to_uppercase().parse().unwrap());
+
+    assert!("85df".parse::<Digest>().is_err());
+    assert!(hex.replace("8", "x").parse::<Digest>().is_err());
+    assert!(hex.replacen("85", "+5", 1).parse::<Digest>().is_err());
+    assert!(Digest::from_slice(&[0; 31]).is_none());
+}
+
+#[test]
+fn test_hash_modes() {
+    let binary = MerkleTree::from_vec(vec!["1", "2", "3", "4"]);
+    assert_eq!("cd53a2ce68e6476c29512ea53c395c7f5d8fbcb4614d89298db14e2a5bdb5456",
+               binary.root_hash().unwrap().to_string());
+
+    let mut compat = MerkleTreeBuilder::new().hash_mode(HashMode::Compat).build();
+    compat.extend(vec!["1", "2", "3"]);
+    compat.push("4");
+    assert_eq!("85df8945419d2b5038f7ac83ec1ec6b8267c40fdb3b1e56ff62f6676eb855e70",
+               compat.root_hash().unwrap().to_string());
+
+    let proof = compat.get_proof_at(1).unwrap();
+    assert!(proof.verify());
+    assert!(!proof.validate(binary.root_hash().unwrap()));
+
+    // Integers are no longer hashed as strings, so only their strings give the old root.
+    let values = vec![1u32, 2, 3, 4];
+    let builder = MerkleTreeBuilder::new().hash_mode(HashMode::Compat);
+    let ints = builder.clone().build_from_vec(values.clone());
+    assert!(ints.root_hash() != compat.root_hash());
+    let strings = builder.build_from_vec(values.iter().map(ToString::to_string).collect());
+    assert_eq!(compat.root_hash(), strings.root_hash());
+}
+
+#[test]
+fn test_domain_separated_hashing() {
+    let db = MerkleTreeBuilder::new()
+        .hash_mode(HashMode::DomainSeparated)
+        .build_from_vec(vec!["1", "2", "3", "4"]);
+    assert_eq!("4c4b77fe3fc6cfb92e4d3c90b5ade42f059a1f112a49827f07edbb7bd4540e7b",
+               db.root_hash().unwrap().to_string());
+    for index in 0..db.len() {
+        assert!(db.get_proof_at(index).unwrap().verify());
+    }
+
+    let h1 = create_leaf_hash(&Sha256, HashMode::DomainSeparated, "1");
+    assert_eq!(Sha256.hash(&[&[0], b"1"]), h1);
+    assert!(create_leaf_hash(&Sha256, HashMode::Binary, "1") != h1);
+    let h2 = create_leaf_hash(&Sha256, HashMode::DomainSeparated, "2");
+    assert_eq!(Sha256.hash(&[&[1], h1.as_ref(), h2.as_ref()]),
+               create_node_hash(&Sha256, HashMode::DomainSeparated, &h1, &h2));
+}
+
+#[test]
+fn test_second_preimage_protection() {
+    for &mode in &[HashMode::Compat, HashMode::DomainSeparated] {
+        let values = vec!["1", "2", "3", "4"].into_iter().map(String::from).collect::<Vec<_>>();
+        let db = MerkleTreeBuilder::new().hash_mode(mode).build_from_vec(values);
+        let root_hash = db.root_hash().unwrap();
+
+        // Present children of the left node as a single leaf of the tree with two leaves.
+        let h1 = create_leaf_hash(&Sha256, mode, &"1");
+        let h2 = create_leaf_hash(&Sha256, mode, &"2");
+        let h3 = create_leaf_hash(&Sha256, mode, &"3");
+        let h4 = create_leaf_hash(&Sha256, mode, &"4");
+        let h34 = create_node_hash(&Sha256, mode, &h3, &h4);
+        let forged = Proof::with_scheme(*root_hash,
+                                        format!("{}{}", h1, h2),
+                                        0,
+                                        2,
+                                        vec![ProofNode::Right(h34)],
+                                        HashScheme::new(Sha256, mode, OddNodePolicy::default()));
+        assert_eq!(mode == HashMode::Compat, forged.validate(root_hash));
+    }
+}
+
+#[test]
+fn test_leaf_encoding() {
+    assert_eq!(&[0, 0, 0, 1], &*1u32.leaf_bytes());
+    assert_eq!(&[0xff, 0xfe], &*(-2i16).leaf_bytes());
+    assert_eq!(&[0, 0, 0, 0, 0, 0, 1, 0], &*256usize.leaf_bytes());
+    assert_eq!(b"1", &*"1".leaf_bytes());
+    assert_eq!(&[1, 2, 3], &*vec![1u8, 2, 3].leaf_bytes());
+    assert!(1u32.leaf_bytes() != "1".leaf_bytes());
+    assert!(1u32.leaf_bytes() != 1u64.leaf_bytes());
+    assert!(("ab", "c").leaf_bytes() != ("a", "bc").leaf_bytes());
+    assert_eq!(&[0, 0, 0, 0, 0, 0, 0, 1, b'a', 0, 0, 0, 0, 0, 0, 0, 1, 7],
+               &*("a", 7u8).leaf_bytes());
+
+    let db = MerkleTree::from_vec(vec![vec![0u8, 159, 146, 150], vec![], vec![255]]);
+    assert_eq!(create_leaf_hash(&Sha256, HashMode::Binary, &[0u8, 159, 146, 150][..]),
+               create_leaf_hash(&Sha256, HashMode::Binary, db.get(0).unwrap()));
+    assert!(db.get_proof(vec![255]).unwrap().verify());
+}
+
+#[test]
+fn test_odd_node_policies() {
+    let root = |policy, values: Vec<u32>| {
+        let db = MerkleTreeBuilder::new().odd_node_policy(policy).build_from_vec(values);
+        *db.root_hash().unwrap()
+    };
+    assert_eq!(root(OddNodePolicy::Duplicate, vec![1, 2, 3]),
+               root(OddNodePolicy::Duplicate, vec![1, 2, 3, 3]));
+    for &policy in &[OddNodePolicy::Promote, OddNodePolicy::Pad] {
+        assert!(root(policy, vec![1, 2, 3]) != root(policy, vec![1, 2, 3, 3]));
+    }
+
+    // Padding must not collide with a real empty leaf.
+    fn padded<T: AsLeafBytes + Clone>(mode: HashMode, values: Vec<T>) -> Digest {
+        let builder = MerkleTreeBuilder::new().hash_mode(mode).odd_node_policy(OddNodePolicy::Pad);
+        *builder.build_from_vec(values).root_hash().unwrap()
+    }
+    for &mode in &[HashMode::Binary, HashMode::Compat, HashMode::DomainSeparated] {
+        assert!(padded(mode, vec!["1", "2", "3"]) != padded(mode, vec!["1", "2", "3", ""]));
+        assert!(padded(mode, vec![vec![1u8], vec![2], vec![3]]) !=
+                padded(mode, vec![vec![1u8], vec![2], vec![3], vec![]]));
+    }
+
+    for &policy in &[OddNodePolicy::Duplicate, OddNodePolicy::Promote, OddNodePolicy::Pad] {
+        let builder = MerkleTreeBuilder::new().odd_node_policy(policy);
+        let mut db = builder.clone().build();
+        for i in 0..20u32 {
+            db.push(i);
+            let expected = builder.clone().build_from_vec((0..i + 1).collect());
+            assert_eq!(expected.root_hash(), db.root_hash());
+            for index in 0..db.len() {
+                let proof = db.get_proof_at(index).unwrap();
+                assert!(proof.verify(), "{:?} {} {}", policy, db.len(), index);
+            }
+        }
+        db.remove(3);
+        let expected = builder.build_from_vec((0..20).filter(|&i| i != 3).collect());
+        assert_eq!(expected.root_hash(), db.root_hash());
+
+        // A proof must not skip or add the entry of the lone node.
+        let proof = db.get_proof_at(18).unwrap();
+        let mut path = proof.path().to_vec();
+        if policy == OddNodePolicy::Promote {
+            path.push(ProofNode::Right(*db.root_hash().unwrap()));
+        } else {
+            path.pop();
+        }
+        let forged = Proof::with_scheme(*db.root_hash().unwrap(),
+                                        18,
+                                        18,
+                                        19,
+                                        path,
+                                        HashScheme::new(Sha256, HashMode::Binary, policy));
+        assert!(!forged.verify());
+    }
+}
+
+#[test]
+fn test_rfc6962_roots() {
+    let data: Vec<Vec<u8>> = vec![vec![],
+                                  vec![0x00],
+                                  vec![0x10],
+                                  vec![0x20, 0x21],
+                                  vec![0x30, 0x31],
+                                  vec![0x40, 0x41, 0x42, 0x43],
+                                  (0x50..0x58).collect(),
+                                  (0x60..0x70).collect()];
+    let roots = ["6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d",
+                 "fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125",
+                 "aeb6bcfe274b70a14fb067a5e5578264db0fa9b51af5e0ba159158f329e06e77",
+                 "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+                 "4e3bbb1f7b478dcfe71fb631631519a3bca12c9aefca1612bfce4c13a86264d4",
+                 "76e67dadbcdf1e10e1b74ddc608abd2f98dfb16fbce75277b5232a127f2087ef",
+                 "ddb89be403809e325750d3d263cd78929c2942b7942a34b77e122c9594a74c8c",
+                 "5dc9da79a70659a9ad559cb701ded9a2ab9d823aad2f4960cfe370eff4604328"];
+
+    let mut db = MerkleTreeBuilder::new()
+        .hash_mode(HashMode::DomainSeparated)
+        .odd_node_policy(OddNodePolicy::Promote)
+        .build();
+    for (value, root) in data.into_iter().zip(roots.iter()) {
+        db.push(value);
+        assert_eq!(root.parse::<Digest>().unwrap(), *db.root_hash().unwrap());
+    }
+}
+
+#[test]
+fn test_send_and_sync() {
+    fn assert_send_sync<S: Send + Sync>(_: &S) {}
+
+    let handle = thread::spawn(|| MerkleTree::from_vec(vec![1, 2, 3, 4]));
+    let db = Arc::new(RwLock::new(handle.join().unwrap()));
+    assert_send_sync(&db);
+
+    let writer = {
+        let db = db.clone();
+        thread::spawn(move || db.write().unwrap().push(5))
+    };
+    writer.join().unwrap();
+    let db = db.read().unwrap();
+    assert_eq!(vec![&1, &2, &3, &4, &5], db.iter().collect::<Vec<_>>());
+    assert!(db.get_proof(5).unwrap().verify());
+}
+
+#[test]
+fn test_large_tree_matches_incremental() {
+    for &policy in &[OddNodePolicy::Duplicate, OddNodePolicy::Promote, OddNodePolicy::Pad] {
+        let builder = MerkleTreeBuilder::new().odd_node_policy(policy);
+        let values = (0..5000u32).collect::<Vec<_>>();
+        let db = builder.clone().build_from_vec(values.clone());
+        let mut expected = builder.build();
+        for value in values {
+            expected.push(value);
+        }
+        assert_eq!(expected.root_hash(), db.root_hash());
+        assert!(db.get_proof_at(4321).unwrap().verify());
+    }
+}
+
+#[test]
+fn test_root_builder() {
+    let empty: MerkleRootBuilder = MerkleRootBuilder::new();
+    assert_eq!(MerkleTree::<u32>::new().root_hash(), Some(&empty.root()));
+
+    for &mode in &[HashMode::Binary, HashMode::Compat, HashMode::DomainSeparated] {
+        for &policy in &[OddNodePolicy::Duplicate, OddNodePolicy::Promote, OddNodePolicy::Pad] {
+            let builder = MerkleTreeBuilder::new().hash_mode(mode).odd_node_policy(policy);
+            let mut root_builder = builder.clone().root_builder();
+            for i in 0..70u32 {
+                root_builder.push(i);
+                let db = builder.clone().build_from_vec((0..i + 1).collect());
+                assert_eq!(db.root_hash(), Some(&root_builder.root()));
+            }
+            assert_eq!(70, root_builder.len());
+        }
+    }
+
+    let mut root_builder = MerkleRootBuilder::new();
+    root_builder.extend((0..1000u64).map(|i| i * i));
+    let db = MerkleTree::from_vec((0..1000u64).map(|i| i * i).collect());
+    assert_eq!(db.root_hash(), Some(&root_builder.root()));
+}
+
+#[test]
+fn test_consistency_proofs() {
+    let data: Vec<Vec<u8>> = vec![vec![],
+                                  vec![0x00],
+                                  vec![0x10],
+                                  vec![0x20, 0x21],
+                                  vec![0x30, 0x31],
+                                  vec![0x40, 0x41, 0x42, 0x43],
+                                  (0x50..0x58).collect(),
+                                  (0x60..0x70).collect()];
+    let builder = MerkleTreeBuilder::new()
+        .hash_mode(HashMode::DomainSeparated)
+        .odd_node_policy(OddNodePolicy::Promote);
+    let db = builder.clone().build_from_vec(data);
+    let path = |hashes: &[&str]| hashes.iter().map(|h| h.parse().unwrap()).collect::<Vec<Digest>>();
+
+    // Test vectors of RFC 6962 implementation of Certificate Transparency.
+    let proof_1_8 = ["96a296d224f285c67bee93c30f8a309157f0daa35dc5b87e410b78630a09cfc7",
+                     "5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e",
+                     "6b47aaf29ee3c2af9af889bc1fb9254dabd31177f16232dd6aab035ca39bf6e4"];
+    let proof_6_8 = ["0ebc5d3437fbe2db158b9f126a1d118e308181031d0a949f8dededebc558ef6a",
+                     "ca854ea128ed050b41b35ffc1b87b8eb2bde461e9e3b5596ece6b9d5975a0ae0",
+                     "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7"];
+    let proof_2_5 = ["5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e",
+                     "bc1a0643b12e4d2d7c77918f44e0f4f79a838b6cf9ec5b5c283e1f4d88599e6b"];
+    let expected = vec![(1, 8, path(&proof_1_8)),
+                        (6, 8, path(&proof_6_8)),
+                        (2, 5, path(&proof_2_5))];
+    for (old_size, new_size, expected) in expected {
+        assert_eq!(&expected[..],
+                   db.get_consistency_proof(old_size, new_size).unwrap().path());
+        let proof = ConsistencyProof::new(old_size, new_size, expected);
+        let old_tree = builder.clone().build_from_vec(db.iter().take(old_size).cloned().collect());
+        let new_tree = builder.clone().build_from_vec(db.iter().take(new_size).cloned().collect());
+        assert!(proof.verify(old_tree.root_hash().unwrap(), new_tree.root_hash().unwrap()));
+        assert!(!proof.verify(new_tree.root_hash().unwrap(), new_tree.root_hash().unwrap()));
+    }
+
+    let db = builder.clone().build_from_vec((0..40u32).collect());
+    let roots = (1..41u32)
+        .map(|size| *builder.clone().build_from_vec((0..size).collect()).root_hash().unwrap())
+        .collect::<Vec<_>>();
+    for new_size in 1..41 {
+        for old_size in 1..new_size + 1 {
+            let proof = db.get_consistency_proof(old_size, new_size).unwrap();
+            assert!(proof.verify(&roots[old_size - 1], &roots[new_size - 1]));
+            if old_size > 1 {
+                assert!(!proof.verify(&roots[old_size - 2], &roots[new_size - 1]));
+            }
+        }
+    }
+
+    assert_eq!(Some(ProofError::IndexOutOfRange), db.get_consistency_proof(0, 5).err());
+    assert_eq!(Some(ProofError::IndexOutOfRange), db.get_consistency_proof(6, 5).err());
+    assert_eq!(Some(ProofError::IndexOutOfRange), db.get_consistency_proof(5, 41).err());
+    assert_eq!(Some(ProofError::UnsupportedPolicy),
+               MerkleTree::from_vec(vec![1, 2, 3]).get_consistency_proof(1, 3).err());
+
+    // Proofs received from elsewhere are verified with the hashing rules of the builder.
+    let builder = builder.hasher(Blake2b);
+    let old_db = builder.clone().build_from_vec((0..3u32).collect());
+    let new_db = builder.clone().build_from_vec((0..7u32).collect());
+    let (old_root, new_root) = (old_db.root_hash().unwrap(), new_db.root_hash().unwrap());
+    let sent = new_db.get_consistency_proof(3, 7).unwrap();
+    assert!(builder.consistency_proof(3, 7, sent.path().to_vec()).verify(old_root, new_root));
+    assert!(!ConsistencyProof::new(3, 7, sent.path().to_vec()).verify(old_root, new_root));
+}
+
+#[test]
+fn test_proofs_at_older_size() {
+    for &policy in &[OddNodePolicy::Duplicate, OddNodePolicy::Promote, OddNodePolicy::Pad] {
+        let builder = MerkleTreeBuilder::new().odd_node_policy(policy);
+        let db = builder.clone().build_from_vec((0..37u32).collect());
+        assert_eq!(builder.clone().build::<u32>().root_hash().cloned(), db.root_at_size(0));
+        for tree_size in 1..38 {
+            let old_db = builder.clone().build_from_vec((0..tree_size as u32).collect());
+            let old_root = old_db.root_hash().unwrap();
+            assert_eq!(Some(old_root), db.root_at_size(tree_size).as_ref());
+            for index in 0..tree_size {
+                let proof = db.get_proof_at_size(index, tree_size).unwrap();
+                assert_eq!(old_db.get_proof_at(index).unwrap().path(), proof.path());
+                assert!(proof.verify_against(old_root).is_ok());
+            }
+        }
+        assert_eq!(None, db.root_at_size(38));
+        assert_eq!(Some(ProofError::IndexOutOfRange), db.get_proof_at_size(5, 5).err());
+        assert_eq!(Some(ProofError::IndexOutOfRange), db.get_proof_at_size(5, 38).err());
+    }
+
+    // Only appending keeps the roots of older sizes.
+    let mut db = MerkleTree::from_vec(vec![1u32, 2, 3]);
+    let old_root = *db.root_hash().unwrap();
+    db.push(4);
+    db.set(0, 9);
+    assert!(Some(old_root) != db.root_at_size(3));
+    assert!(db.get_proof_at_size(1, 3).unwrap().verify_against(&old_root).is_err());
+    assert_eq!(MerkleTree::from_vec(vec![9u32, 2, 3]).root_hash().cloned(), db.root_at_size(3));
+}
+
+#[test]
+fn test_multiproofs() {
+    let db = MerkleTree::from_vec((0..1000u32).collect());
+    let root_hash = db.root_hash().unwrap();
+    let indices = (0..500).map(|i| i * 2).collect::<Vec<_>>();
+    let proof = db.get_multiproof(&indices).unwrap();
+    assert!(proof.validate(root_hash));
+    assert_eq!(500, proof.values().len());
+
+    let single_proofs_size = indices.iter()
+        .map(|&index| db.get_proof_at(index).unwrap().path().len())
+        .sum::<usize>();
+    assert_eq!(5000, single_proofs_size);
+    assert_eq!(500, proof.hashes().len());
+
+    let wrong_value = MultiProof::with_scheme(*root_hash,
+                                              vec![(0, 0), (2, 3)],
+                                              1000,
+                                              db.get_multiproof(&[0, 2]).unwrap().hashes().to_vec(),
+                                              HashScheme::with_hasher(Sha256));
+    assert!(!wrong_value.validate(root_hash));
+
+    for &policy in &[OddNodePolicy::Duplicate, OddNodePolicy::Promote, OddNodePolicy::Pad] {
+        for size in 1..20u32 {
+            let db = MerkleTreeBuilder::new()
+                .odd_node_policy(policy)
+                .build_from_vec((0..size).collect());
+            for step in 1..4 {
+                for first in 0..size as usize {
+                    let indices = (first..size as usize).step_by(step).collect::<Vec<_>>();
+                    let proof = db.get_multiproof(&indices).unwrap();
+                    assert!(proof.validate(db.root_hash().unwrap()));
+                }
+            }
+        }
+    }
+
+    assert_eq!(Some(ProofError::IndexOutOfRange), db.get_multiproof(&[]).err());
+    assert_eq!(Some(ProofError::IndexOutOfRange), db.get_multiproof(&[3, 1000]).err());
+
+    // Proofs received from elsewhere are validated with the hashing rules of the builder.
+    let builder = MerkleTreeBuilder::new()
+        .hasher(Blake2b)
+        .odd_node_policy(OddNodePolicy::Promote);
+    let db = builder.clone().build_from_vec((0..7u32).collect());
+    let root_hash = db.root_hash().unwrap();
+    let sent = db.get_multiproof(&[1, 4, 6]).unwrap();
+    let proof = builder.multiproof(*root_hash, sent.values().to_vec(), 7, sent.hashes().to_vec());
+    assert!(proof.validate(root_hash));
+    let other = MerkleTreeBuilder::new().hasher(Blake2b);
+    let proof = other.multiproof(*root_hash, sent.values().to_vec(), 7, sent.hashes().to_vec());
+    assert!(!proof.validate(root_hash));
+
+    // Untrusted tree size must not overflow.
+    let proof = builder.multiproof(*root_hash,
+                                   sent.values().to_vec(),
+                                   usize::MAX,
+                                   sent.hashes().to_vec());
+    assert!(!proof.validate(root_hash));
+}
+
+#[test]
+fn test_absence_proofs() {
+    let mut revoked = MerkleTreeBuilder::new()
+        .hash_mode(HashMode::Binary)
+        .build_sorted_from_vec(vec![50u64, 10, 30, 70, 90, 30]);
+    assert_eq!(vec![&10, &30, &50, &70, &90], revoked.iter().collect::<Vec<_>>());
+    assert!(revoked.insert(60));
+    assert!(!revoked.insert(60));
+    assert!(revoked.remove(&60));
+    assert!(!revoked.contains(&60));
+    let root_hash = *revoked.root_hash().unwrap();
+    let expected = MerkleTreeBuilder::new()
+        .hash_mode(HashMode::DomainSeparated)
+        .build_from_vec(vec![10u64, 30, 50, 70, 90]);
+    assert_eq!(expected.root_hash(), Some(&root_hash));
+    assert!(SortedMerkleTree::<u64>::default().is_empty());
+
+    for &(value, left, right) in &[(5, None, Some(10)),
+                                   (40, Some(30), Some(50)),
+                                   (95, Some(90), None)] {
+        let proof = revoked.get_absence_proof(value).unwrap();
+        assert_eq!(left.as_ref(), proof.left().map(|p| p.value()));
+        assert_eq!(right.as_ref(), proof.right().map(|p| p.value()));
+        assert!(proof.validate(&root_hash, 5));
+        assert!(!proof.validate(&root_hash, 6));
+    }
+    assert_eq!(Some(ProofError::ValuePresent), revoked.get_absence_proof(50).err());
+    assert!(revoked.get_proof(50).unwrap().verify());
+
+    // Neighbours which are not adjacent or don't surround the value prove nothing.
+    let proof_at = |value| revoked.get_proof(value).ok();
+    assert!(!AbsenceProof::new(40, proof_at(10), proof_at(50)).validate(&root_hash, 5));
+    assert!(!AbsenceProof::new(60, proof_at(30), proof_at(50)).validate(&root_hash, 5));
+    assert!(!AbsenceProof::new(60, proof_at(70), None).validate(&root_hash, 5));
+    assert!(!AbsenceProof::new(60, None, proof_at(30)).validate(&root_hash, 5));
+    assert!(!AbsenceProof::<u64>::new(60, None, None).validate(&root_hash, 5));
+
+    revoked.insert(40);
+    let proof = revoked.get_absence_proof(35).unwrap();
+    assert!(!proof.validate(&root_hash, 5));
+    assert!(proof.validate(revoked.root_hash().unwrap(), 6));
+}
+
+#[test]
+fn test_absence_proof_forgery() {
+    let keys = vec![b"10".to_vec(), b"20".to_vec(), b"30".to_vec(), b"40".to_vec()];
+    let leaf = |key: &[u8]| create_leaf_hash(&Sha256, HashMode::Binary, key);
+    let concat = |left: &Digest, right: &Digest| [left.as_ref(), right.as_ref()].concat();
+    let (h0, h1, h2, h3) = (leaf(&keys[0]), leaf(&keys[1]), leaf(&keys[2]), leaf(&keys[3]));
+    let fake_left = concat(&h0, &h1);
+    let fake_right = concat(&h2, &h3);
+    let absent = [&fake_left[..], &[0]].concat();
+    assert!(fake_left < absent && absent < fake_right);
+
+    // In Binary mode the children of a node pass for a leaf of a smaller tree.
+    let binary = MerkleTree::from_vec(keys.clone());
+    let root_hash = *binary.root_hash().unwrap();
+    let n0 = create_node_hash(&Sha256, HashMode::Binary, &h0, &h1);
+    let n1 = create_node_hash(&Sha256, HashMode::Binary, &h2, &h3);
+    let forged = |left: Vec<u8>, right: Vec<u8>| {
+        AbsenceProof::new(absent.clone(),
+                          Some(Proof::new(root_hash, left, 0, 2, vec![ProofNode::Right(n1)])),
+                          Some(Proof::new(root_hash, right, 1, 2, vec![ProofNode::Left(n0)])))
+    };
+    let proof = forged(fake_left.clone(), fake_right.clone());
+    assert!(proof.left().unwrap().validate_strict(&root_hash));
+    assert!(proof.right().unwrap().validate_strict(&root_hash));
+    assert!(!proof.validate(&root_hash, 2));
+    assert!(!proof.validate(&root_hash, 4));
+
+    // Sorted trees always separate leaves from nodes, whatever the builder says.
+    let sorted = MerkleTreeBuilder::new().hash_mode(HashMode::Binary).build_sorted_from_vec(keys);
+    let root_hash = *sorted.root_hash().unwrap();
+    assert!(root_hash != *binary.root_hash().unwrap());
+    let builder = MerkleTreeBuilder::new().hash_mode(HashMode::DomainSeparated);
+    let ds = |value: Vec<u8>, index, path| builder.proof(root_hash, value, index, 2, path);
+    let proof = AbsenceProof::new(absent.clone(),
+                                  Some(ds(fake_left, 0, vec![ProofNode::Right(n1)])),
+                                  Some(ds(fake_right, 1, vec![ProofNode::Left(n0)])));
+    assert!(!proof.validate(&root_hash, 2));
+    assert!(!proof.validate(&root_hash, 4));
+    assert!(sorted.get_absence_proof(absent).unwrap().validate(&root_hash, 4));
+}
+
+#[test]
+fn test_range_proofs() {
+    for &policy in &[OddNodePolicy::Duplicate, OddNodePolicy::Promote, OddNodePolicy::Pad] {
+        let values = (0..23u32).collect::<Vec<_>>();
+        let db = MerkleTreeBuilder::new().odd_node_policy(policy).build_from_vec(values.clone());
+        let root_hash = db.root_hash().unwrap();
+        for start in 0..23 {
+            for end in start + 1..24 {
+                let proof = db.get_range_proof(start, end).unwrap();
+                assert!(proof.validate(root_hash, 23, start, &values[start..end]));
+            }
+        }
+    }
+
+    let values = (0..23u32).collect::<Vec<_>>();
+    let db = MerkleTree::from_vec(values.clone());
+    let root_hash = db.root_hash().unwrap();
+    let proof = db.get_range_proof(5, 10).unwrap();
+    assert!(proof.validate(root_hash, 23, 5, &[5, 6, 7, 8, 9]));
+    assert!(!proof.validate(root_hash, 23, 5, &[5, 6, 8, 9]));
+    assert!(!proof.validate(root_hash, 23, 5, &[5, 6, 7, 8, 9, 10]));
+    assert!(!proof.validate(root_hash, 23, 5, &[5, 6, 7, 100, 8, 9]));
+    assert!(!proof.validate(root_hash, 23, 5, &[5, 7, 6, 8, 9]));
+    assert!(!proof.validate::<u32>(root_hash, 23, 5, &[]));
+    assert!(!proof.validate(root_hash, 22, 5, &[5, 6, 7, 8, 9]));
+    assert!(!proof.validate(root_hash, 23, 4, &[4, 5, 6, 7, 8]));
+    assert!(!db.get_range_proof(4, 9).unwrap().validate(root_hash, 23, 4, &[5, 6, 7, 8, 9]));
+    assert!(!db.get_range_proof(4, 9).unwrap().validate(root_hash, 23, 5, &[5, 6, 7, 8, 9]));
+
+    assert_eq!(Some(ProofError::IndexOutOfRange), db.get_range_proof(5, 5).err());
+    assert_eq!(Some(ProofError::IndexOutOfRange), db.get_range_proof(5, 24).err());
+    assert_eq!(Some(ProofError::EmptyTree),
+               MerkleTree::<u32>::new().get_range_proof(0, 1).err());
+
+    // Proofs received from elsewhere are validated with the hashing rules of the builder.
+    let builder = MerkleTreeBuilder::new()
+        .hasher(Blake2b)
+        .hash_mode(HashMode::DomainSeparated);
+    let db = builder.clone().build_from_vec(values.clone());
+    let root_hash = db.root_hash().unwrap();
+    let sent = db.get_range_proof(2, 5).unwrap();
+    let proof = builder.range_proof(2, 5, 23, sent.hashes().to_vec());
+    assert!(proof.validate(root_hash, 23, 2, &values[2..5]));
+    let other = MerkleTreeBuilder::new().hasher(Blake2b);
+    let proof = other.range_proof(2, 5, 23, sent.hashes().to_vec());
+    assert!(!proof.validate(root_hash, 23, 2, &values[2..5]));
+
+    // Untrusted tree size must not overflow.
+    let proof = builder.range_proof(2, 5, usize::MAX, sent.hashes().to_vec());
+    assert!(!proof.validate(root_hash, usize::MAX, 2, &values[2..5]));
+
+    // A proof built for another size of the tree can't insert a duplicate of the lone
+    // leaf with OddNodePolicy::Duplicate.
+    let db = MerkleTree::from_vec(vec![1u32, 2, 3]);
+    let root_hash = db.root_hash().unwrap();
+    let sent = db.get_range_proof(2, 3).unwrap();
+    let builder = MerkleTreeBuilder::new();
+    let proof = builder.range_proof(2, 4, 4, sent.hashes().to_vec());
+    assert!(!proof.validate(root_hash, 3, 2, &[3, 3]));
+
+    // In HashMode::Binary the children of nodes can't pass for leaves of a smaller tree.
+    let keys = vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec(), b"d".to_vec()];
+    let db = MerkleTree::from_vec(keys.clone());
+    let root_hash = db.root_hash().unwrap();
+    let leaf = |key: &[u8]| create_leaf_hash(&Sha256, HashMode::Binary, key);
+    let fake_leaves = vec![[leaf(&keys[0]).as_ref(), leaf(&keys[1]).as_ref()].concat(),
+                           [leaf(&keys[2]).as_ref(), leaf(&keys[3]).as_ref()].concat()];
+    let proof = builder.range_proof(0, 2, 2, vec![]);
+    assert!(!proof.validate(root_hash, 4, 0, &fake_leaves));
+}
+
+#[test]
+fn test_proofs_from_builder() {
+    let builder = MerkleTreeBuilder::new()
+        .hasher(Blake2b)
+        .hash_mode(HashMode::DomainSeparated)
+        .odd_node_policy(OddNodePolicy::Promote);
+    let db = builder.clone().build_from_vec((0..7u32).collect());
+    let root_hash = db.root_hash().unwrap();
 
+    let sent = db.get_proof(6).unwrap();
+    let proof = builder.proof(*root_hash, 6, 6, 7, sent.path().to_vec());
+    assert!(proof.validate_strict(root_hash));
+    let other = MerkleTreeBuilder::new().hasher(Blake2b);
+    assert!(!other.proof(*root_hash, 6, 6, 7, sent.path().to_vec()).validate(root_hash));
 }